@@ -0,0 +1,185 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, from_slice, to_binary, Addr, CosmosMsg, QuerierWrapper, StdResult, Uint128, WasmMsg,
+};
+use cw2::ContractVersion;
+use cw20::{Cw20ExecuteMsg, Denom};
+
+use crate::error::ContractError;
+
+//cw2 name and major.minor version of the bank contract this game is written against
+pub const BANK_CONTRACT_NAME: &str = "crates.io:amg-bank";
+pub const BANK_INTERFACE_VERSION: &str = "0.1";
+//cw2 storage key of ContractVersion
+const CONTRACT_INFO_KEY: &[u8] = b"contract_info";
+
+/// Execute interface the game expects from the bank contract.
+#[cw_serde]
+pub enum BankExecuteMsg {
+    Deposit {},
+    Withdraw {},
+    /// Sends amount of denom to the sender.
+    BorrowBalance {
+        denom: Denom,
+        amount: Uint128,
+    },
+    /// Takes the attached funds, or the cw20 amount when sent through Receive.
+    PayBack {},
+}
+
+/// Query interface the game expects from the bank contract.
+#[cw_serde]
+pub enum BankQueryMsg {
+    /// Return type: LiquidityResponse.
+    Liquidity { denom: Denom },
+    /// Returns what the borrower has drawn and paid back in total.
+    /// Return type: BorrowedResponse.
+    Borrowed { borrower: String, denom: Denom },
+    /// Return type: LpTokenInfoResponse.
+    LpTokenInfo {},
+}
+
+#[cw_serde]
+pub struct LiquidityResponse {
+    //pool balance of the denom
+    pub available: Uint128,
+    //lent to bettings not settled yet
+    pub borrowed: Uint128,
+}
+
+#[cw_serde]
+pub struct BorrowedResponse {
+    //BorrowBalance sent to the borrower
+    pub borrowed: Uint128,
+    //PayBack received from the borrower
+    pub repaid: Uint128,
+}
+
+#[cw_serde]
+pub struct LpTokenInfoResponse {
+    pub address: Addr,
+    pub total_supply: Uint128,
+}
+
+pub fn borrow_msg(bank: &Addr, denom: &Denom, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: bank.to_string(),
+        msg: to_binary(&BankExecuteMsg::BorrowBalance {
+            denom: denom.clone(),
+            amount,
+        })?,
+        funds: vec![],
+    }))
+}
+
+//cw20 goes through Send so the bank contract sees PayBack in its Receive hook
+pub fn pay_back_msg(bank: &Addr, denom: &Denom, amount: Uint128) -> StdResult<CosmosMsg> {
+    let msg = match denom {
+        Denom::Native(denom) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: bank.to_string(),
+            msg: to_binary(&BankExecuteMsg::PayBack {})?,
+            funds: vec![coin(amount.u128(), denom)],
+        }),
+        Denom::Cw20(token) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: bank.to_string(),
+                amount,
+                msg: to_binary(&BankExecuteMsg::PayBack {})?,
+            })?,
+            funds: vec![],
+        }),
+    };
+    Ok(msg)
+}
+
+pub fn query_liquidity(
+    querier: &QuerierWrapper,
+    bank: &Addr,
+    denom: &Denom,
+) -> Result<LiquidityResponse, ContractError> {
+    querier
+        .query_wasm_smart(
+            bank,
+            &BankQueryMsg::Liquidity {
+                denom: denom.clone(),
+            },
+        )
+        .map_err(|err| ContractError::BankQueryFailed {
+            reason: err.to_string(),
+        })
+}
+
+pub fn query_borrowed(
+    querier: &QuerierWrapper,
+    bank: &Addr,
+    borrower: &Addr,
+    denom: &Denom,
+) -> Result<BorrowedResponse, ContractError> {
+    querier
+        .query_wasm_smart(
+            bank,
+            &BankQueryMsg::Borrowed {
+                borrower: borrower.to_string(),
+                denom: denom.clone(),
+            },
+        )
+        .map_err(|err| ContractError::BankQueryFailed {
+            reason: err.to_string(),
+        })
+}
+
+pub fn query_lp_token_info(
+    querier: &QuerierWrapper,
+    bank: &Addr,
+) -> Result<LpTokenInfoResponse, ContractError> {
+    querier
+        .query_wasm_smart(bank, &BankQueryMsg::LpTokenInfo {})
+        .map_err(|err| ContractError::BankQueryFailed {
+            reason: err.to_string(),
+        })
+}
+
+//cw2 version of the bank contract, read raw so a contract without the bank queries still answers
+pub fn query_bank_version(
+    querier: &QuerierWrapper,
+    bank: &Addr,
+) -> Result<ContractVersion, ContractError> {
+    let raw = querier
+        .query_wasm_raw(bank, CONTRACT_INFO_KEY)
+        .map_err(|err| ContractError::InvalidBankContract {
+            reason: err.to_string(),
+        })?
+        .ok_or_else(|| ContractError::InvalidBankContract {
+            reason: "no cw2 contract info".to_string(),
+        })?;
+    from_slice(&raw).map_err(|err| ContractError::InvalidBankContract {
+        reason: err.to_string(),
+    })
+}
+
+/*
+Reject a bank contract that does not speak BankExecuteMsg/BankQueryMsg.
+The cw2 name must match and the version must share BANK_INTERFACE_VERSION as major.minor.
+ */
+pub fn check_bank_contract(
+    querier: &QuerierWrapper,
+    bank: &Addr,
+) -> Result<ContractVersion, ContractError> {
+    let version = query_bank_version(querier, bank)?;
+    if version.contract != BANK_CONTRACT_NAME {
+        return Err(ContractError::InvalidBankContract {
+            reason: format!("{} is not {}", version.contract, BANK_CONTRACT_NAME),
+        });
+    }
+    let major_minor = version.version.splitn(3, '.').take(2).collect::<Vec<_>>();
+    match major_minor.join(".") == BANK_INTERFACE_VERSION {
+        true => Ok(version),
+        false => Err(ContractError::InvalidBankContract {
+            reason: format!(
+                "version {} is not compatible with {}",
+                version.version, BANK_INTERFACE_VERSION
+            ),
+        }),
+    }
+}
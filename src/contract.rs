@@ -1,14 +1,44 @@
+use crate::bank::{
+    borrow_msg, check_bank_contract, pay_back_msg, query_bank_version, query_borrowed,
+    query_liquidity, query_lp_token_info,
+};
 use crate::error::ContractError;
-use crate::helper::{check_admin, check_denom, check_duration, check_lock};
-use crate::msg::{AMGBankMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::helper::{
+    add_denom_amount, check_admin, check_credit_line, check_denom, check_denom_config,
+    check_duration, check_duration_length, check_exposure, check_fee_late, check_funds,
+    check_house_mode, check_lock, check_odds_mode, check_pool, check_pool_share, check_price_gap,
+    check_price_guard, check_price_precision, from_price_units, median, payout_rate,
+    query_denom_balance, round_price_units, to_price_units, transfer_msg,
+};
+use crate::msg::{
+    AccountPayout, BankInfoResponse, DenomResponse, DurationResponse, ExecuteMsg,
+    FeedRoundResponse, FeederSubmissionResponse, HousePoolResponse, InstantiateMsg,
+    LpSharesResponse, MarketResponse, OddsResponse, PendingDrawResponse, PendingPayBackResponse,
+    PreviewSettlementResponse, PriceResponse, QueryMsg, QuoteResponse, ReceiveMsg,
+    RoundResultResponse, SettlementResponse, SolvencyResponse,
+};
 
-use crate::state::{load_state, save_state, Betting, Position, State, BALANCE, BETTINGS, PRICES};
+use crate::oracle::query_oracle_price;
+use crate::settlement::{resolve_open_heights, settle_betting, settle_pending, BankTransfer};
+use crate::state::{
+    bettings, close_betting, denom_key, height_bettings, load_market, load_state, next_betting_id,
+    open_betting, releasable_balance, release_balance, remove_draw, save_market, save_state,
+    BankLedger, BetStatus, Betting, DenomAmount, DenomConfig, DurationConfig, Exposure,
+    ExposureLimits, FeedRound, FeederSubmission, Market, MissedRoundPolicy, OddsMode, PendingDraw,
+    PendingPayBack, PoolMode, Position, PriceGuard, PriceSource, SettleTask, State, BALANCE,
+    BANK_LEDGER, CW20_TOKENS, DENOMS, DRAWS, DURATIONS, EXPOSURE, EXPOSURE_LIMITS, FEEDERS,
+    FEEDER_SUBMISSIONS, FEED_ROUND, FEED_ROUNDS, HELD_BALANCE, HOUSE_POOLS, LP_SHARES, MARKETS,
+    OPEN_HEIGHTS, OPEN_STAKE, PAY_BACKS, PRICES, ROUND_RESULTS, SETTLED_HEIGHTS, SETTLE_CURSOR,
+    SETTLE_QUEUE,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, to_binary, BankMsg, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo,
-    Response, StdResult, Uint128, WasmMsg,
+    from_binary, to_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Reply,
+    Response, StdError, StdResult, Storage, SubMsg, SubMsgResult, Uint128,
 };
+use cw20::{Cw20ReceiveMsg, Denom};
+use cw_storage_plus::Bound;
 use std::cmp::Ordering::*;
 
 use cw2::set_contract_version;
@@ -17,30 +47,85 @@ use cw2::set_contract_version;
 const CONTRACT_NAME: &str = "crates.io:fx-game";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+//price = 1.00001 => 100001
+const DEFAULT_PRICE_PRECISION: u32 = 5;
+const DEFAULT_SETTLE_LIMIT: u64 = 100;
+//block_height + 1 = 6s
+const DEFAULT_DURATIONS: [u64; 2] = [30, 50];
+//block_height + 1 = 6s, 600 blocks = 1 hour
+const DEFAULT_REFUND_TIMEOUT: u64 = 600;
+//10000 = 100%
+const MAX_POOL_SHARE_BPS: u64 = 10000;
+
+//blocks an oracle price or a feeder submission counts for, when max_age is None
+const DEFAULT_PRICE_MAX_AGE: u64 = 10;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     let bank_contract = deps.api.addr_validate(&msg.bank_contract_address)?;
+    let price_source = match &msg.oracle_address {
+        Some(address) => PriceSource::Oracle {
+            address: deps.api.addr_validate(address)?,
+            max_age: None,
+        },
+        None => PriceSource::Admin {},
+    };
+    let max_pool_share_bps = msg.max_pool_share_bps.unwrap_or(MAX_POOL_SHARE_BPS);
+    check_pool_share(max_pool_share_bps)?;
     let state = State {
         admin: vec![info.sender.clone()],
-        denom: msg.denom.clone(),
-        fee_late: msg.fee_late,
-        minimum_amount: Uint128::new(msg.minimum_amount as u128),
         bank_contract,
-        latest_price: Uint128::new(0),
-        lock: false,
+        settle_limit: msg.settle_limit.unwrap_or(DEFAULT_SETTLE_LIMIT),
+        missed_round_policy: msg
+            .missed_round_policy
+            .unwrap_or(MissedRoundPolicy::Refund {}),
+        refund_timeout: msg.refund_timeout.unwrap_or(DEFAULT_REFUND_TIMEOUT),
+        max_pool_share_bps,
+        price_source,
+        price_guard: PriceGuard::default(),
+        odds_mode: OddsMode::Fixed {},
+        pool_mode: msg.pool_mode.unwrap_or(PoolMode::Bank {}),
     };
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     save_state(deps.storage, &state)?;
 
+    DENOMS.save(
+        deps.storage,
+        &msg.denom,
+        &DenomConfig {
+            minimum_amount: Uint128::new(msg.minimum_amount as u128),
+            maximum_amount: None,
+        },
+    )?;
+    create_market(
+        deps.storage,
+        &msg.market_id,
+        msg.fee_late,
+        DEFAULT_DURATIONS.to_vec(),
+        msg.price_precision,
+    )?;
+    if msg.price.is_zero() {
+        return Err(ContractError::ZeroPrice {});
+    }
+    let mut market = load_market(deps.storage, &msg.market_id)?;
+    market.latest_price = to_price_units(msg.price, market.price_precision)?;
+    market.latest_price_height = env.block.height;
+    save_market(deps.storage, &msg.market_id, &market)?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender)
+        .add_attribute("market_id", msg.market_id)
         .add_attribute("denom", msg.denom)
         .add_attribute("fee_late", msg.fee_late.to_string()))
 }
@@ -53,13 +138,75 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Betting { position, duration } => betting(deps, env, info, position, duration),
-        ExecuteMsg::Setting { price, lock } => setting(deps, env, info, price, lock),
+        ExecuteMsg::Betting {
+            market_id,
+            position,
+            duration,
+        } => betting(deps, env, info, market_id, position, duration),
+        ExecuteMsg::Setting {
+            market_id,
+            price,
+            lock,
+        } => setting(deps, env, info, market_id, price, lock),
+        ExecuteMsg::UpdatePrice { market_id } => update_price(deps, env, market_id),
+        ExecuteMsg::SubmitPrice { market_id, price } => {
+            submit_price(deps, env, info, market_id, price)
+        }
+        ExecuteMsg::Receive(msg) => receive(deps, env, info, msg),
+        ExecuteMsg::Deposit {} => deposit(deps, info),
+        ExecuteMsg::Withdraw { denom, shares } => withdraw(deps, info, denom, shares),
+        ExecuteMsg::Claim { denom, amount } => claim(deps, info, denom, amount),
+        ExecuteMsg::Settle { limit } => settle(deps, env, limit),
+        ExecuteMsg::RetryPayBack { limit } => retry_pay_back(deps, limit),
+        ExecuteMsg::RetryDraw { limit } => retry_draw(deps, limit),
+        ExecuteMsg::RefundExpired {
+            market_id,
+            target_height,
+        } => refund_expired(deps, env, info, market_id, target_height),
+        ExecuteMsg::SetSettleLimit { limit } => set_settle_limit(deps, info, limit),
+        ExecuteMsg::SetMissedRoundPolicy { policy } => set_missed_round_policy(deps, info, policy),
+        ExecuteMsg::SetRefundTimeout { blocks } => set_refund_timeout(deps, info, blocks),
+        ExecuteMsg::SetMaxPoolShare { bps } => set_max_pool_share(deps, info, bps),
 
-        ExecuteMsg::SetFeeLate { fee_late } => set_fee_late(deps, env, info, fee_late),
-        ExecuteMsg::SetMinimumAmount { amount } => set_minimum_amount(deps, env, info, amount),
+        ExecuteMsg::AddMarket {
+            market_id,
+            fee_late,
+            durations,
+            price_precision,
+        } => add_market(deps, info, market_id, fee_late, durations, price_precision),
+        ExecuteMsg::SetDuration {
+            market_id,
+            duration,
+            fee_late,
+        } => set_duration(deps, info, market_id, duration, fee_late),
+        ExecuteMsg::RemoveDuration {
+            market_id,
+            duration,
+        } => remove_duration(deps, info, market_id, duration),
+        ExecuteMsg::SetDenom {
+            denom,
+            minimum_amount,
+            maximum_amount,
+        } => set_denom(deps, info, denom, minimum_amount, maximum_amount),
+        ExecuteMsg::RemoveDenom { denom } => remove_denom(deps, info, denom),
+        ExecuteMsg::SetCw20Token {
+            address,
+            minimum_amount,
+            maximum_amount,
+        } => set_cw20_token(deps, info, address, minimum_amount, maximum_amount),
+        ExecuteMsg::RemoveCw20Token { address } => remove_cw20_token(deps, info, address),
         ExecuteMsg::SetBankContract { address } => set_bank_contract(deps, info, address),
         ExecuteMsg::AddAdmin { address } => add_admin(deps, info, address),
+        ExecuteMsg::SetPriceSource { source } => set_price_source(deps, info, source),
+        ExecuteMsg::AddFeeder { address } => add_feeder(deps, info, address),
+        ExecuteMsg::RemoveFeeder { address } => remove_feeder(deps, info, address),
+        ExecuteMsg::SetLock { market_id, lock } => set_lock(deps, info, market_id, lock),
+        ExecuteMsg::SetPriceGuard { guard } => set_price_guard(deps, info, guard),
+        ExecuteMsg::SetExposureLimits { denom, limits } => {
+            set_exposure_limits(deps, info, denom, limits)
+        }
+        ExecuteMsg::SetOddsMode { mode } => set_odds_mode(deps, info, mode),
+        ExecuteMsg::SetCreditLine { denom, amount } => set_credit_line(deps, info, denom, amount),
     }
 }
 
@@ -67,95 +214,474 @@ fn betting(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    market_id: String,
     position: String,
     duration: u64,
 ) -> Result<Response, ContractError> {
+    let coin = check_funds(&info)?;
+    place_betting(
+        deps,
+        env,
+        info.sender,
+        Denom::Native(coin.denom),
+        coin.amount,
+        market_id,
+        position,
+        duration,
+    )
+}
+
+fn deposit(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let coin = check_funds(&info)?;
+    place_deposit(deps, info.sender, Denom::Native(coin.denom), coin.amount)
+}
+
+//cw20 betting and deposit, info.sender is the token contract
+fn receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let bettor = deps.api.addr_validate(&wrapper.sender)?;
+
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::Betting {
+            market_id,
+            position,
+            duration,
+        } => place_betting(
+            deps,
+            env,
+            bettor,
+            Denom::Cw20(info.sender),
+            wrapper.amount,
+            market_id,
+            position,
+            duration,
+        ),
+        ReceiveMsg::Deposit {} => {
+            place_deposit(deps, bettor, Denom::Cw20(info.sender), wrapper.amount)
+        }
+    }
+}
+
+//shares = amount * (total_shares + 1000) / (value - pending draws + 1), 1000 per unit for the first deposit
+fn place_deposit(
+    deps: DepsMut,
+    depositor: Addr,
+    denom: Denom,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_house_mode(&state)?;
+    check_denom(deps.storage, &denom, amount)?;
+
+    let key = denom_key(&denom);
+    let mut pool = HOUSE_POOLS
+        .may_load(deps.storage, &key)?
+        .unwrap_or_default();
+    let debt = BANK_LEDGER
+        .may_load(deps.storage, &key)?
+        .unwrap_or_default()
+        .pending_draw;
+    //the debt above value would be paid with the new deposit
+    if debt > pool.value {
+        return Err(ContractError::PoolInDebt {});
+    }
+    let shares = pool.shares_for(amount, debt);
+    if shares.is_zero() {
+        return Err(ContractError::InvalidMinimumAmount {});
+    }
+
+    pool.total_shares += shares;
+    pool.value += amount;
+    HOUSE_POOLS.save(deps.storage, &key, &pool)?;
+    LP_SHARES.update(deps.storage, (&depositor, &key), |owned| -> StdResult<_> {
+        Ok(owned.unwrap_or_default() + shares)
+    })?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("method", "deposit".to_string()),
+        ("account", depositor.to_string()),
+        ("denom", key),
+        ("amount", amount.to_string()),
+        ("shares", shares.to_string()),
+    ]))
+}
+
+/*
+Pay the shares their part of the free liquidity, value - reserved - pending draws,
+and burn only the shares worth what was paid. The rest keeps its claim on the reserved amount.
+ */
+fn withdraw(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Denom,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_house_mode(&state)?;
+
+    let key = denom_key(&denom);
+    let owned = LP_SHARES
+        .may_load(deps.storage, (&info.sender, &key))?
+        .unwrap_or_default();
+    if shares.is_zero() || shares > owned {
+        return Err(ContractError::InvalidShares {});
+    }
+
+    let mut pool = HOUSE_POOLS.load(deps.storage, &key)?;
+    let ledger = BANK_LEDGER
+        .may_load(deps.storage, &key)?
+        .unwrap_or_default();
+    let free = pool
+        .value
+        .saturating_sub(ledger.reserved + ledger.pending_draw);
+    let amount = pool.free_value_of(shares, free);
+    if amount.is_zero() {
+        return Err(ContractError::InvalidWithdrawBalance {});
+    }
+    let burned = pool.shares_of(amount, ledger.pending_draw)?;
+
+    pool.total_shares -= burned;
+    pool.value -= amount;
+    HOUSE_POOLS.save(deps.storage, &key, &pool)?;
+    let remain = owned - burned;
+    match remain.is_zero() {
+        true => LP_SHARES.remove(deps.storage, (&info.sender, &key)),
+        false => LP_SHARES.save(deps.storage, (&info.sender, &key), &remain)?,
+    }
+
+    Ok(Response::new()
+        .add_message(transfer_msg(&info.sender, &denom, amount)?)
+        .add_attributes(vec![
+            ("method", "withdraw".to_string()),
+            ("account", info.sender.to_string()),
+            ("denom", key),
+            ("shares", burned.to_string()),
+            ("amount", amount.to_string()),
+        ]))
+}
+
+//a betting that passed every check, not saved yet
+struct PreparedBet {
+    position: Position,
+    base_price: Uint128,
+    target_height: u64,
+    win_amount: Uint128,
+    borrow_amount: Uint128,
+    //open stake of the bettor including this betting
+    open_stake: Uint128,
+    //exposure of the target height including this betting
+    exposure: Exposure,
+}
+
+/*
+Every check and calculation of a betting.
+Betting, Receive and QueryMsg::QuoteBet all go through here so a quote cannot drift from
+the betting it describes. Without a bettor the open stake limit is checked against this betting only.
+ */
+#[allow(clippy::too_many_arguments)]
+fn prepare_bet(
+    deps: Deps,
+    env: &Env,
+    bettor: Option<&Addr>,
+    denom: &Denom,
+    betting_amount: Uint128,
+    market_id: &str,
+    position: &str,
+    duration: u64,
+) -> Result<PreparedBet, ContractError> {
     let state = load_state(deps.storage)?;
-    check_lock(&state)?;
-    check_denom(&info, &state)?;
-    check_duration(duration)?;
+    let market = load_market(deps.storage, market_id)?;
+    check_lock(&market)?;
+    check_denom(deps.storage, denom, betting_amount)?;
+    let config = check_duration(deps.storage, market_id, duration)?;
 
     let now_height = env.block.height;
-    let denom = &info.funds[0];
 
-    let base_price = match PRICES.load(deps.storage, now_height) {
+    let base_price = match PRICES.load(deps.storage, (market_id, now_height)) {
         Ok(price) => price,
-        Err(_) => state.latest_price,
+        Err(_) => {
+            check_price_gap(&state, &market, now_height)?;
+            market.latest_price
+        }
     };
 
     let target_height = now_height + duration;
-    let betting_amount = denom.amount;
+    let position = Position::new(position)?;
 
-    //3/100 = 0.03
-    let fee_late = Decimal::from_ratio(state.fee_late, Uint128::new(100));
-    //borrow amount = 0.97 * betting_amount
-    let borrow_amount = (Decimal::one().checked_sub(fee_late)).unwrap() * betting_amount;
+    let stake_key = denom_key(denom);
+    let exposure = EXPOSURE
+        .may_load(deps.storage, (market_id, target_height, &stake_key))?
+        .unwrap_or_default();
+    let mut new_exposure = exposure.clone();
+    new_exposure.add(&position, betting_amount);
+
+    //borrow amount = 0.97 * betting_amount with fee_late 3 in Fixed mode
+    //priced on the exposure with this betting in it, a large bet moves its own odds
+    let payout_rate = payout_rate(&state.odds_mode, config.fee_late, &new_exposure, &position);
+    let borrow_amount = payout_rate * betting_amount;
     let win_amount = betting_amount + borrow_amount;
+    let ledger = BANK_LEDGER
+        .may_load(deps.storage, &stake_key)?
+        .unwrap_or_default();
+    check_credit_line(&ledger, borrow_amount)?;
+    check_pool(
+        deps.storage,
+        &deps.querier,
+        &state,
+        denom,
+        &ledger,
+        borrow_amount,
+    )?;
+
+    let open_stake = match bettor {
+        Some(bettor) => OPEN_STAKE
+            .may_load(deps.storage, (bettor, &stake_key))?
+            .unwrap_or_default(),
+        None => Uint128::zero(),
+    } + betting_amount;
+    let limits = EXPOSURE_LIMITS
+        .may_load(deps.storage, &stake_key)?
+        .unwrap_or_default();
+    check_exposure(
+        &limits,
+        betting_amount,
+        open_stake,
+        &exposure,
+        &new_exposure,
+    )?;
+
+    Ok(PreparedBet {
+        position,
+        base_price,
+        target_height,
+        win_amount,
+        borrow_amount,
+        open_stake,
+        exposure: new_exposure,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn place_betting(
+    deps: DepsMut,
+    env: Env,
+    bettor: Addr,
+    denom: Denom,
+    betting_amount: Uint128,
+    market_id: String,
+    position: String,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    let prepared = prepare_bet(
+        deps.as_ref(),
+        &env,
+        Some(&bettor),
+        &denom,
+        betting_amount,
+        &market_id,
+        &position,
+        duration,
+    )?;
+    let now_height = env.block.height;
+    let target_height = prepared.target_height;
+    let base_price = prepared.base_price;
+    let id = next_betting_id(deps.storage)?;
     //option 업데이트
     {
-        let position = Position::new(position.as_str())?;
         let betting = Betting::new(
-            info.sender.clone(),
-            position,
+            id,
+            market_id.clone(),
+            bettor.clone(),
+            denom.clone(),
+            prepared.position,
             betting_amount,
-            win_amount,
+            prepared.win_amount,
             base_price,
             now_height,
             target_height,
         );
+        open_betting(
+            deps.storage,
+            &betting,
+            prepared.open_stake,
+            &prepared.exposure,
+        )?;
 
-        BETTINGS.update(deps.storage, target_height, |exsists| -> StdResult<_> {
-            match exsists {
-                Some(mut bettings) => {
-                    bettings.push(betting);
-                    Ok(bettings)
-                }
-                None => Ok(vec![betting]),
-            }
+        let key = (market_id.as_str(), target_height);
+        bettings().save(deps.storage, id, &betting)?;
+        OPEN_HEIGHTS.update(deps.storage, key, |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default() + 1)
         })?;
     }
 
     // save_state(deps.storage, &state)?;
 
-    let msg = AMGBankMsg::BorrowBalance {
-        amount: borrow_amount,
-    };
-
-    Ok(Response::new()
-        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: state.bank_contract.to_string(),
-            msg: to_binary(&msg)?,
-            funds: vec![],
-        }))
-        .add_attributes(vec![
-            ("method", "betting".to_string()),
-            ("position", position),
-            ("account", info.sender.to_string()),
-            ("betting_amount", betting_amount.to_string()),
-            ("start_height", now_height.to_string()),
-            ("target_height", target_height.to_string()),
-            ("price", base_price.to_string()),
-        ]))
+    //the pool part is reserved in open_betting, nothing moves until the height is settled
+    Ok(Response::new().add_attributes(vec![
+        ("method", "betting".to_string()),
+        ("betting_id", id.to_string()),
+        ("market_id", market_id),
+        ("position", position),
+        ("account", bettor.to_string()),
+        ("denom", denom_key(&denom)),
+        ("betting_amount", betting_amount.to_string()),
+        ("reserved_amount", prepared.borrow_amount.to_string()),
+        ("start_height", now_height.to_string()),
+        ("target_height", target_height.to_string()),
+        ("price", base_price.to_string()),
+    ]))
 }
 
-/*price = 1.00001 => 100001 */
 fn setting(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    price: Uint128,
+    market_id: String,
+    price: Decimal,
     lock: Option<bool>,
 ) -> Result<Response, ContractError> {
     //
-    let mut state = load_state(deps.storage)?;
+    let state = load_state(deps.storage)?;
+    let mut market = load_market(deps.storage, &market_id)?;
 
     check_admin(&info, &state)?;
-    match lock {
-        Some(lock) => state.lock = lock,
-        None => {}
+    //see update_price and submit_price for the other sources
+    match state.price_source {
+        PriceSource::Admin {} => {}
+        PriceSource::Oracle { .. } => return Err(ContractError::OracleMode {}),
+        PriceSource::Feeders { .. } => return Err(ContractError::FeederMode {}),
+    }
+    if let Some(lock) = lock {
+        market.lock = lock;
+    }
+    let price = to_price_units(price, market.price_precision)?;
+    record_price(deps, env, &state, &market_id, market, price, "setting")
+}
+
+fn update_price(deps: DepsMut, env: Env, market_id: String) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    let market = load_market(deps.storage, &market_id)?;
+    let (oracle, max_age) = match &state.price_source {
+        PriceSource::Oracle { address, max_age } => {
+            (address.clone(), max_age.unwrap_or(DEFAULT_PRICE_MAX_AGE))
+        }
+        _ => return Err(ContractError::NotOracleMode {}),
+    };
+
+    //UpdatePrice is open to anyone, an old oracle price must not become the round price
+    let oracle_price = query_oracle_price(&deps.querier, &oracle, &market_id)?;
+    if env.block.height.saturating_sub(oracle_price.height) > max_age {
+        return Err(ContractError::StaleOraclePrice {
+            height: oracle_price.height,
+        });
+    }
+    let price = round_price_units(oracle_price.price, market.price_precision)?;
+    record_price(deps, env, &state, &market_id, market, price, "update_price")
+}
+
+fn submit_price(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: String,
+    price: Decimal,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    let market = load_market(deps.storage, &market_id)?;
+    let price = round_price_units(price, market.price_precision)?;
+    let (quorum, max_age) = match state.price_source {
+        PriceSource::Feeders { quorum, max_age } => {
+            (quorum, max_age.unwrap_or(DEFAULT_PRICE_MAX_AGE))
+        }
+        _ => return Err(ContractError::NotFeederMode {}),
+    };
+    if !FEEDERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::OnlyFeeder {});
     }
+
+    //a feeder can replace its own submission until the round is finalized
+    let round = FEED_ROUND.may_load(deps.storage, &market_id)?.unwrap_or(1);
+    let submission = FeederSubmission {
+        price,
+        height: env.block.height,
+    };
+    FEEDER_SUBMISSIONS.save(deps.storage, (&market_id, round, &info.sender), &submission)?;
+
+    //older submissions of the round neither count toward quorum nor the median
+    let fresh_from = env.block.height.saturating_sub(max_age);
+    let prices = FEEDER_SUBMISSIONS
+        .prefix((&market_id, round))
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, submission)) => submission.height >= fresh_from,
+            Err(_) => true,
+        })
+        .map(|item| item.map(|(_, submission)| submission.price))
+        .collect::<StdResult<Vec<Uint128>>>()?;
+
+    if (prices.len() as u32) < quorum {
+        FEED_ROUND.save(deps.storage, &market_id, &round)?;
+        return Ok(Response::new()
+            .add_attribute("action", "submit_price")
+            .add_attribute("market_id", market_id)
+            .add_attribute("feeder", info.sender)
+            .add_attribute("round", round.to_string())
+            .add_attribute("submissions", prices.len().to_string()));
+    }
+
+    let feed_round = FeedRound {
+        submissions: prices.len() as u32,
+        price: median(prices),
+        height: env.block.height,
+    };
+    FEED_ROUNDS.save(deps.storage, (&market_id, round), &feed_round)?;
+    FEED_ROUND.save(deps.storage, &market_id, &(round + 1))?;
+
+    let response = record_price(
+        deps,
+        env,
+        &state,
+        &market_id,
+        market,
+        feed_round.price,
+        "submit_price",
+    )?;
+    Ok(response
+        .add_attribute("feeder", info.sender)
+        .add_attribute("round", round.to_string())
+        .add_attribute("submissions", feed_round.submissions.to_string()))
+}
+
+fn record_price(
+    deps: DepsMut,
+    env: Env,
+    state: &State,
+    market_id: &str,
+    mut market: Market,
+    price: Uint128,
+    action: &str,
+) -> Result<Response, ContractError> {
     let now_height = env.block.height;
 
+    if let Err(err) = check_price_guard(state, &market, price, now_height) {
+        if !state.price_guard.lock_on_violation {
+            return Err(err);
+        }
+        //keep the lock, drop the price
+        market.lock = true;
+        save_market(deps.storage, market_id, &market)?;
+        return Ok(Response::new()
+            .add_attribute("action", action)
+            .add_attribute("market_id", market_id)
+            .add_attribute("price", price.to_string())
+            .add_attribute("guard", err.to_string())
+            .add_attribute("lock", "true"));
+    }
+
     // Save the new price
     /*
     If you set the price at the current block height,
@@ -163,102 +689,381 @@ fn setting(
     so we add + 1 to allow them to bet at the same price.
      */
     //next round setting
-    PRICES.save(deps.storage, now_height + 1, &price)?;
-
-    let mut return_balance = Uint128::new(0);
-    let mut bank_msgs = vec![];
-
-    let mut attrs = vec![("action".to_string(), "setting".to_string())];
-    let round_price = PRICES.load(deps.storage, env.block.height);
-    match round_price {
-        Ok(round_price) => {
-            attrs.push(("round_price".to_string(), round_price.to_string()));
-            let bettings = BETTINGS
-                .load(deps.storage, now_height)
-                .unwrap_or_else(|_| vec![]);
-
-            if !bettings.is_empty() {
-                for betting in bettings {
-                    let base_price = betting.base_price;
-                    let win_position = match base_price.cmp(&round_price) {
-                        Less => Position::Long,
-                        Equal => Position::Eqaul,
-                        Greater => Position::Short,
-                    };
-
-                    if win_position != betting.position {
-                        return_balance += betting.win_amount;
-                        continue;
-                    }
-
-                    let bank_msg = CosmosMsg::Bank(BankMsg::Send {
-                        to_address: betting.address.to_string(),
-                        amount: vec![coin(betting.win_amount.u128(), "uconst")],
-                    });
-
-                    bank_msgs.push(bank_msg);
-
-                    attrs.push((betting.address.to_string(), betting.win_amount.to_string()))
-                }
-
-                BETTINGS.remove(deps.storage, now_height);
+    PRICES.save(deps.storage, (market_id, now_height + 1), &price)?;
+
+    let mut attrs = vec![
+        ("action".to_string(), action.to_string()),
+        ("market_id".to_string(), market_id.to_string()),
+        ("price".to_string(), price.to_string()),
+    ];
+    if let Some(round_price) = PRICES.may_load(deps.storage, (market_id, now_height))? {
+        attrs.push(("round_price".to_string(), round_price.to_string()));
+    }
+
+    //every open height up to now, including the ones missed by earlier settings
+    let resolved = resolve_open_heights(
+        deps.storage,
+        market_id,
+        now_height,
+        &state.missed_round_policy,
+        state.settle_limit,
+    )?;
+    attrs.push(("resolved_heights".to_string(), resolved.to_string()));
+
+    market.latest_price = price;
+    market.latest_price_height = now_height;
+
+    save_market(deps.storage, market_id, &market)?;
+
+    //settlement only runs in ExecuteMsg::Settle, the price path never waits on the bank
+    Ok(Response::new().add_attributes(attrs))
+}
+
+fn settle(deps: DepsMut, env: Env, limit: u64) -> Result<Response, ContractError> {
+    if limit == 0 {
+        return Err(ContractError::InvalidSettleLimit {});
+    }
+    let state = load_state(deps.storage)?;
+
+    //heights left open by a price update that hit the limit
+    let markets = MARKETS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, Market)>>>()?;
+    let mut resolved = 0;
+    for (market_id, market) in markets {
+        //the price written by the last Setting before an outage is final once its height is reached
+        let priced_height = PRICES
+            .prefix(&market_id)
+            .keys(
+                deps.storage,
+                None,
+                Some(Bound::exclusive(env.block.height)),
+                Order::Descending,
+            )
+            .next()
+            .transpose()?
+            .unwrap_or(0);
+        resolved += resolve_open_heights(
+            deps.storage,
+            &market_id,
+            market.latest_price_height.max(priced_height),
+            &state.missed_round_policy,
+            limit - resolved,
+        )?;
+    }
+    let outcome = settle_pending(deps.storage, &env, limit)?;
+    let mut attrs = vec![
+        ("action".to_string(), "settle".to_string()),
+        ("resolved_heights".to_string(), resolved.to_string()),
+        ("settled".to_string(), outcome.settled.to_string()),
+    ];
+    attrs.extend(outcome.attrs);
+    settle_response(&state, outcome.transfers, attrs)
+}
+
+fn settle_response(
+    state: &State,
+    transfers: Vec<BankTransfer>,
+    attrs: Vec<(String, String)>,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new();
+    //one net PayBack or BorrowBalance per settled height and denom
+    for transfer in transfers {
+        let msg = match transfer {
+            //a rejected PayBack stays in PAY_BACKS and does not undo the payouts
+            BankTransfer::PayBack(id, DenomAmount { denom, amount }) => {
+                SubMsg::reply_always(pay_back_msg(&state.bank_contract, &denom, amount)?, id)
+            }
+            //a rejected BorrowBalance stays in DRAWS, the winnings of its height stay held
+            BankTransfer::Draw(id, DenomAmount { denom, amount }) => {
+                SubMsg::reply_always(borrow_msg(&state.bank_contract, &denom, amount)?, id)
             }
+        };
+        response = response.add_submessage(msg);
+    }
+    Ok(response.add_attributes(attrs))
+}
+
+fn retry_pay_back(deps: DepsMut, limit: Option<u32>) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let pay_backs = PAY_BACKS
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<(u64, PendingPayBack)>>>()?;
+    if pay_backs.is_empty() {
+        return Err(ContractError::NoPayBackToRetry {});
+    }
+
+    let mut response = Response::new();
+    for (id, pay_back) in pay_backs.iter() {
+        response = response.add_submessage(SubMsg::reply_always(
+            pay_back_msg(&state.bank_contract, &pay_back.denom, pay_back.amount)?,
+            *id,
+        ));
+    }
+    Ok(response.add_attributes(vec![
+        ("method", "retry_pay_back".to_string()),
+        ("pay_backs", pay_backs.len().to_string()),
+    ]))
+}
+
+fn retry_draw(deps: DepsMut, limit: Option<u32>) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let draws = DRAWS
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<(u64, PendingDraw)>>>()?;
+    if draws.is_empty() {
+        return Err(ContractError::NoDrawToRetry {});
+    }
+    if state.pool_mode == (PoolMode::House {}) {
+        return cover_house_draws(deps, draws);
+    }
+
+    let mut response = Response::new();
+    for (id, draw) in draws.iter() {
+        response = response.add_submessage(SubMsg::reply_always(
+            borrow_msg(&state.bank_contract, &draw.denom, draw.amount)?,
+            *id,
+        ));
+    }
+    Ok(response.add_attributes(vec![
+        ("method", "retry_draw".to_string()),
+        ("draws", draws.len().to_string()),
+    ]))
+}
+
+//house pool mode has no bank to draw from, the free liquidity of the pool pays the draws it can
+fn cover_house_draws(
+    deps: DepsMut,
+    draws: Vec<(u64, PendingDraw)>,
+) -> Result<Response, ContractError> {
+    let mut covered = vec![];
+    for (id, draw) in draws {
+        let denom = denom_key(&draw.denom);
+        let mut pool = HOUSE_POOLS
+            .may_load(deps.storage, &denom)?
+            .unwrap_or_default();
+        let mut ledger = BANK_LEDGER
+            .may_load(deps.storage, &denom)?
+            .unwrap_or_default();
+        if pool.value.saturating_sub(ledger.reserved) < draw.amount {
+            continue;
         }
+        pool.value -= draw.amount;
+        ledger.pending_draw = ledger.pending_draw.saturating_sub(draw.amount);
+        HOUSE_POOLS.save(deps.storage, &denom, &pool)?;
+        BANK_LEDGER.save(deps.storage, &denom, &ledger)?;
+        remove_draw(deps.storage, id, &draw)?;
+        covered.push(id.to_string());
+    }
+    if covered.is_empty() {
+        return Err(ContractError::NotEnoughPool {});
+    }
+    Ok(Response::new().add_attributes(vec![
+        ("method", "retry_draw".to_string()),
+        ("covered", covered.join(",")),
+    ]))
+}
 
-        Err(_) => {
-            //how many
-            let mut before_bettings = vec![];
-            for i in 1..=5 {
-                let before_betting = BETTINGS
-                    .load(deps.storage, env.block.height - i)
-                    .unwrap_or_else(|_| vec![]);
-                before_bettings.push(before_betting)
-            }
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match DRAWS.has(deps.storage, msg.id) {
+        true => draw_reply(deps, msg.id, msg.result),
+        false => pay_back_reply(deps, msg.id, msg.result),
+    }
+}
+
+/*
+BorrowBalance SubMsgs reply always, a rejected draw never reverts the settlement.
+Accepted => the entry is removed and the held winnings of its height can be claimed,
+rejected => it stays for RetryDraw and the winnings stay held.
+ */
+fn draw_reply(deps: DepsMut, id: u64, result: SubMsgResult) -> Result<Response, ContractError> {
+    let mut draw = DRAWS.load(deps.storage, id)?;
+    let denom = denom_key(&draw.denom);
 
-            match before_bettings.is_empty() {
-                true => {}
-                false => {
-                    let before_bettings = before_bettings
-                        .into_iter()
-                        .flatten()
-                        .collect::<Vec<Betting>>();
-                    for betting in before_bettings {
-                        //betting amount 는 수수료 포함 금액
-
-                        let bank_msg = CosmosMsg::Bank(BankMsg::Send {
-                            to_address: betting.address.to_string(),
-                            amount: vec![coin(betting.amount.u128(), "uconst")],
-                        });
-
-                        let borrowed_amount = betting.win_amount - betting.amount;
-                        return_balance += borrowed_amount;
-                        bank_msgs.push(bank_msg)
-                    }
-                    BETTINGS.remove(deps.storage, env.block.height - 1)
-                }
-            };
+    let status = match result {
+        SubMsgResult::Ok(_) => {
+            BANK_LEDGER.update(deps.storage, &denom, |ledger| -> StdResult<_> {
+                let mut ledger = ledger.unwrap_or_default();
+                ledger.pending_draw = ledger.pending_draw.saturating_sub(draw.amount);
+                ledger.drawn += draw.amount;
+                Ok(ledger)
+            })?;
+            remove_draw(deps.storage, id, &draw)?;
+            "drawn"
+        }
+        SubMsgResult::Err(reason) => {
+            draw.attempts += 1;
+            draw.error = Some(reason);
+            DRAWS.save(deps.storage, id, &draw)?;
+            "pending"
         }
     };
 
-    state.latest_price = price;
+    Ok(Response::new().add_attributes(vec![
+        ("method", "draw_reply".to_string()),
+        ("draw_id", id.to_string()),
+        ("market_id", draw.market_id),
+        ("height", draw.height.to_string()),
+        ("denom", denom),
+        ("amount", draw.amount.to_string()),
+        ("status", status.to_string()),
+    ]))
+}
+
+/*
+PayBack SubMsgs reply always.
+Accepted => the entry is removed and the amount moves from pending_pay_back to paid_back,
+rejected => it stays for RetryPayBack.
+ */
+fn pay_back_reply(deps: DepsMut, id: u64, result: SubMsgResult) -> Result<Response, ContractError> {
+    let mut pay_back = PAY_BACKS.load(deps.storage, id)?;
+    let denom = denom_key(&pay_back.denom);
+    let mut ledger = BANK_LEDGER
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_default();
 
-    save_state(deps.storage, &state)?;
-    let response = match return_balance.is_zero() {
-        true => Response::new()
-            .add_messages(bank_msgs)
-            .add_attributes(attrs),
-
-        false => Response::new()
-            .add_messages(bank_msgs)
-            .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: state.bank_contract.to_string(),
-                msg: to_binary(&AMGBankMsg::PayBack {})?,
-                funds: vec![coin(return_balance.u128(), "uconst")],
-            }))
-            .add_attributes(attrs),
+    let status = match result {
+        SubMsgResult::Ok(_) => {
+            ledger.pending_pay_back = ledger.pending_pay_back.saturating_sub(pay_back.amount);
+            ledger.paid_back += pay_back.amount;
+            PAY_BACKS.remove(deps.storage, id);
+            "paid"
+        }
+        SubMsgResult::Err(reason) => {
+            pay_back.attempts += 1;
+            pay_back.error = Some(reason);
+            PAY_BACKS.save(deps.storage, id, &pay_back)?;
+            "pending"
+        }
     };
-    Ok(response)
+    BANK_LEDGER.save(deps.storage, &denom, &ledger)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("method", "pay_back_reply".to_string()),
+        ("pay_back_id", id.to_string()),
+        ("market_id", pay_back.market_id),
+        ("height", pay_back.height.to_string()),
+        ("denom", denom),
+        ("amount", pay_back.amount.to_string()),
+        ("status", status.to_string()),
+    ]))
+}
+
+/*
+No round price ever arrived for target_height (feeder down, contract locked ...).
+Once refund_timeout blocks have passed the bettor takes the stake back
+and the reserved amount is released, nothing moves with the bank.
+ */
+fn refund_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_id: String,
+    target_height: u64,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    let key = (market_id.as_str(), target_height);
+
+    let expire_height = target_height.saturating_add(state.refund_timeout);
+    if env.block.height < expire_height {
+        return Err(ContractError::RefundNotExpired { expire_height });
+    }
+    //a height already queued for settlement is paid out by Settle
+    if PRICES.has(deps.storage, key) || !OPEN_HEIGHTS.has(deps.storage, key) {
+        return Err(ContractError::RefundNotAvailable {});
+    }
+
+    let (refunds, remains): (Vec<Betting>, Vec<Betting>) = height_bettings(
+        deps.storage,
+        &market_id,
+        target_height,
+        Some(&BetStatus::Open),
+        None,
+        None,
+    )?
+    .into_iter()
+    .partition(|betting| betting.address == info.sender);
+    if refunds.is_empty() {
+        return Err(ContractError::NoBettingToRefund {});
+    }
+
+    match remains.is_empty() {
+        true => OPEN_HEIGHTS.remove(deps.storage, key),
+        false => OPEN_HEIGHTS.save(deps.storage, key, &(remains.len() as u64))?,
+    }
+
+    let mut refund_amount = vec![];
+    for betting in refunds.iter() {
+        close_betting(deps.storage, betting)?;
+        let mut refunded = betting.clone();
+        refunded.status = BetStatus::Refunded;
+        bettings().replace(deps.storage, betting.id, Some(&refunded), Some(betting))?;
+        add_denom_amount(&mut refund_amount, &betting.denom, betting.amount);
+    }
+
+    let mut response = Response::new();
+    let mut refund_attr = vec![];
+    for DenomAmount { denom, amount } in refund_amount {
+        refund_attr.push(format!("{}{}", amount, denom_key(&denom)));
+        response = response.add_message(transfer_msg(&info.sender, &denom, amount)?);
+    }
+    Ok(response.add_attributes(vec![
+        ("method", "refund_expired".to_string()),
+        ("market_id", market_id.clone()),
+        ("account", info.sender.to_string()),
+        ("target_height", target_height.to_string()),
+        ("bettings", refunds.len().to_string()),
+        ("refund_amount", refund_attr.join(",")),
+    ]))
 }
+
+fn claim(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Denom,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let denom_key = denom_key(&denom);
+    release_balance(deps.storage, &info.sender, &denom_key)?;
+    let key = (&info.sender, denom_key.as_str());
+    let balance = BALANCE.may_load(deps.storage, key)?.unwrap_or_default();
+    if balance.is_zero() {
+        return Err(ContractError::NoBalance {});
+    }
+
+    //None => claim everything
+    let amount = amount.unwrap_or(balance);
+    if amount.is_zero() || amount > balance {
+        return Err(ContractError::InvalidClaimAmount {});
+    }
+
+    let remain = balance - amount;
+    match remain.is_zero() {
+        true => BALANCE.remove(deps.storage, key),
+        false => BALANCE.save(deps.storage, key, &remain)?,
+    }
+    BANK_LEDGER.update(deps.storage, &denom_key, |ledger| -> StdResult<_> {
+        let mut ledger = ledger.unwrap_or_default();
+        ledger.claimable = ledger.claimable.saturating_sub(amount);
+        Ok(ledger)
+    })?;
+
+    Ok(Response::new()
+        .add_message(transfer_msg(&info.sender, &denom, amount)?)
+        .add_attributes(vec![
+            ("method", "claim".to_string()),
+            ("account", info.sender.to_string()),
+            ("denom", denom_key.clone()),
+            ("amount", amount.to_string()),
+            ("remain", remain.to_string()),
+        ]))
+}
+
 fn add_admin(deps: DepsMut, info: MessageInfo, address: String) -> Result<Response, ContractError> {
     let mut state = load_state(deps.storage)?;
     check_admin(&info, &state)?;
@@ -269,32 +1074,170 @@ fn add_admin(deps: DepsMut, info: MessageInfo, address: String) -> Result<Respon
     Ok(Response::new())
 }
 
-fn set_fee_late(
+//save the market with every duration sharing one fee_late
+fn create_market(
+    storage: &mut dyn Storage,
+    market_id: &str,
+    fee_late: u8,
+    durations: Vec<u64>,
+    price_precision: Option<u32>,
+) -> Result<(), ContractError> {
+    if market_id.is_empty() || market_id.contains('/') {
+        return Err(ContractError::InvalidMarketId {});
+    }
+    check_fee_late(fee_late)?;
+    for duration in durations.iter() {
+        check_duration_length(*duration)?;
+    }
+    let price_precision = price_precision.unwrap_or(DEFAULT_PRICE_PRECISION);
+    check_price_precision(price_precision)?;
+    let market = Market {
+        lock: false,
+        price_precision,
+        latest_price: Uint128::new(0),
+        latest_price_height: 0,
+    };
+    save_market(storage, market_id, &market)?;
+
+    let config = DurationConfig { fee_late };
+    for duration in durations {
+        DURATIONS.save(storage, (market_id, duration), &config)?;
+    }
+    Ok(())
+}
+
+fn add_market(
     deps: DepsMut,
-    _env: Env,
     info: MessageInfo,
+    market_id: String,
     fee_late: u8,
+    durations: Vec<u64>,
+    price_precision: Option<u32>,
 ) -> Result<Response, ContractError> {
-    let mut state = load_state(deps.storage)?;
+    let state = load_state(deps.storage)?;
     check_admin(&info, &state)?;
-    state.fee_late = fee_late;
-    save_state(deps.storage, &state)?;
+    if MARKETS.has(deps.storage, &market_id) {
+        return Err(ContractError::MarketExists { market_id });
+    }
+    create_market(
+        deps.storage,
+        &market_id,
+        fee_late,
+        durations,
+        price_precision,
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "add_market")
+        .add_attribute("market_id", market_id))
+}
+
+fn set_duration(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_id: String,
+    duration: u64,
+    fee_late: u8,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    load_market(deps.storage, &market_id)?;
+    check_fee_late(fee_late)?;
+    check_duration_length(duration)?;
+    let config = DurationConfig { fee_late };
+    DURATIONS.save(deps.storage, (&market_id, duration), &config)?;
     Ok(Response::new()
-        .add_attribute("method", "set_fee_late")
+        .add_attribute("method", "set_duration")
+        .add_attribute("market_id", market_id)
+        .add_attribute("duration", duration.to_string())
         .add_attribute("fee_late", fee_late.to_string()))
 }
 
-fn set_minimum_amount(
+fn remove_duration(
     deps: DepsMut,
-    _env: Env,
     info: MessageInfo,
-    amount: u64,
+    market_id: String,
+    duration: u64,
 ) -> Result<Response, ContractError> {
-    let mut state = load_state(deps.storage)?;
+    let state = load_state(deps.storage)?;
     check_admin(&info, &state)?;
-    state.minimum_amount = amount.into();
-    save_state(deps.storage, &state)?;
-    Ok(Response::new())
+    //bettings already placed keep their win_amount
+    DURATIONS.remove(deps.storage, (&market_id, duration));
+    Ok(Response::new()
+        .add_attribute("method", "remove_duration")
+        .add_attribute("market_id", market_id)
+        .add_attribute("duration", duration.to_string()))
+}
+
+fn set_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    minimum_amount: u64,
+    maximum_amount: Option<u64>,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    let config = DenomConfig {
+        minimum_amount: Uint128::new(minimum_amount as u128),
+        maximum_amount: maximum_amount.map(|amount| Uint128::new(amount as u128)),
+    };
+    check_denom_config(&config)?;
+    DENOMS.save(deps.storage, &denom, &config)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_denom")
+        .add_attribute("denom", denom)
+        .add_attribute("minimum_amount", minimum_amount.to_string()))
+}
+
+fn remove_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    //open bettings in this denom are still paid out in it
+    DENOMS.remove(deps.storage, &denom);
+    Ok(Response::new()
+        .add_attribute("method", "remove_denom")
+        .add_attribute("denom", denom))
+}
+
+fn set_cw20_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    minimum_amount: u64,
+    maximum_amount: Option<u64>,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    let token = deps.api.addr_validate(&address)?;
+    let config = DenomConfig {
+        minimum_amount: Uint128::new(minimum_amount as u128),
+        maximum_amount: maximum_amount.map(|amount| Uint128::new(amount as u128)),
+    };
+    check_denom_config(&config)?;
+    CW20_TOKENS.save(deps.storage, &token, &config)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_cw20_token")
+        .add_attribute("token", token)
+        .add_attribute("minimum_amount", minimum_amount.to_string()))
+}
+
+fn remove_cw20_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    let token = deps.api.addr_validate(&address)?;
+    //open bettings in this token are still paid out in it
+    CW20_TOKENS.remove(deps.storage, &token);
+    Ok(Response::new()
+        .add_attribute("method", "remove_cw20_token")
+        .add_attribute("token", token))
 }
 
 fn set_bank_contract(
@@ -304,27 +1247,369 @@ fn set_bank_contract(
 ) -> Result<Response, ContractError> {
     let mut state = load_state(deps.storage)?;
     check_admin(&info, &state)?;
+    //a PayBack, draw or reservation of the old bank would be settled with the new one
+    let ledgers = BANK_LEDGER
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, BankLedger)>>>()?;
+    if !PAY_BACKS.is_empty(deps.storage)
+        || !DRAWS.is_empty(deps.storage)
+        || ledgers.iter().any(|(_, ledger)| !ledger.reserved.is_zero())
+    {
+        return Err(ContractError::BankInUse {});
+    }
     let pool_contract = deps.api.addr_validate(address.as_str())?;
+    let version = check_bank_contract(&deps.querier, &pool_contract)?;
     state.bank_contract = pool_contract;
     save_state(deps.storage, &state)?;
-    Ok(Response::new().add_attribute("bank_contract", address))
+    Ok(Response::new().add_attributes(vec![
+        ("bank_contract", address),
+        ("bank_version", version.version),
+    ]))
+}
+
+fn set_settle_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    limit: u64,
+) -> Result<Response, ContractError> {
+    if limit == 0 {
+        return Err(ContractError::InvalidSettleLimit {});
+    }
+    let mut state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    state.settle_limit = limit;
+    save_state(deps.storage, &state)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_settle_limit")
+        .add_attribute("settle_limit", limit.to_string()))
+}
+
+fn set_missed_round_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    policy: MissedRoundPolicy,
+) -> Result<Response, ContractError> {
+    let mut state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    state.missed_round_policy = policy;
+    save_state(deps.storage, &state)?;
+    Ok(Response::new().add_attribute("method", "set_missed_round_policy"))
+}
+
+fn set_refund_timeout(
+    deps: DepsMut,
+    info: MessageInfo,
+    blocks: u64,
+) -> Result<Response, ContractError> {
+    let mut state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    state.refund_timeout = blocks;
+    save_state(deps.storage, &state)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_refund_timeout")
+        .add_attribute("refund_timeout", blocks.to_string()))
+}
+
+fn set_max_pool_share(
+    deps: DepsMut,
+    info: MessageInfo,
+    bps: u64,
+) -> Result<Response, ContractError> {
+    let mut state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    check_pool_share(bps)?;
+    state.max_pool_share_bps = bps;
+    save_state(deps.storage, &state)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_max_pool_share")
+        .add_attribute("max_pool_share_bps", bps.to_string()))
+}
+
+fn set_price_source(
+    deps: DepsMut,
+    info: MessageInfo,
+    source: PriceSource,
+) -> Result<Response, ContractError> {
+    let mut state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    state.price_source = match source {
+        PriceSource::Admin {} => PriceSource::Admin {},
+        PriceSource::Oracle { address, max_age } => {
+            let address = deps.api.addr_validate(address.as_str())?;
+            //must answer the price query of every market before it can drive settlement
+            let market_ids = MARKETS
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<String>>>()?;
+            for market_id in market_ids {
+                query_oracle_price(&deps.querier, &address, &market_id)?;
+            }
+            PriceSource::Oracle { address, max_age }
+        }
+        PriceSource::Feeders { quorum, max_age } => {
+            check_quorum(deps.as_ref(), quorum)?;
+            PriceSource::Feeders { quorum, max_age }
+        }
+    };
+    save_state(deps.storage, &state)?;
+    Ok(Response::new().add_attribute("method", "set_price_source"))
+}
+
+fn check_quorum(deps: Deps, quorum: u32) -> Result<(), ContractError> {
+    let feeders = FEEDERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    match quorum >= 1 && quorum as usize <= feeders {
+        true => Ok(()),
+        false => Err(ContractError::InvalidQuorum {}),
+    }
+}
+
+fn add_feeder(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    let feeder = deps.api.addr_validate(address.as_str())?;
+    FEEDERS.save(deps.storage, &feeder, &true)?;
+    Ok(Response::new()
+        .add_attribute("method", "add_feeder")
+        .add_attribute("feeder", feeder))
+}
+
+fn remove_feeder(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    let feeder = deps.api.addr_validate(address.as_str())?;
+    FEEDERS.remove(deps.storage, &feeder);
+    //drop its vote from the open rounds
+    let feed_rounds = FEED_ROUND
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, u64)>>>()?;
+    for (market_id, round) in feed_rounds {
+        FEEDER_SUBMISSIONS.remove(deps.storage, (&market_id, round, &feeder));
+    }
+
+    if let PriceSource::Feeders { quorum, .. } = state.price_source {
+        check_quorum(deps.as_ref(), quorum)?;
+    }
+    Ok(Response::new()
+        .add_attribute("method", "remove_feeder")
+        .add_attribute("feeder", feeder))
+}
+
+fn set_price_guard(
+    deps: DepsMut,
+    info: MessageInfo,
+    guard: PriceGuard,
+) -> Result<Response, ContractError> {
+    let mut state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    state.price_guard = guard;
+    save_state(deps.storage, &state)?;
+    Ok(Response::new().add_attribute("method", "set_price_guard"))
+}
+
+fn set_exposure_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Denom,
+    limits: ExposureLimits,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    let key = denom_key(&denom);
+    EXPOSURE_LIMITS.save(deps.storage, &key, &limits)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_exposure_limits")
+        .add_attribute("denom", key))
+}
+
+fn set_odds_mode(
+    deps: DepsMut,
+    info: MessageInfo,
+    mode: OddsMode,
+) -> Result<Response, ContractError> {
+    let mut state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    check_odds_mode(&mode)?;
+    state.odds_mode = mode;
+    save_state(deps.storage, &state)?;
+    Ok(Response::new().add_attribute("method", "set_odds_mode"))
+}
+
+fn set_credit_line(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Denom,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    let key = denom_key(&denom);
+    let mut ledger = BANK_LEDGER
+        .may_load(deps.storage, &key)?
+        .unwrap_or_default();
+    ledger.credit_line = amount;
+    BANK_LEDGER.save(deps.storage, &key, &ledger)?;
+    Ok(Response::new().add_attributes(vec![
+        ("method", "set_credit_line".to_string()),
+        ("denom", key),
+    ]))
 }
 
-// ######## TODO!!! Oracle version Setting
+fn set_lock(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_id: String,
+    lock: bool,
+) -> Result<Response, ContractError> {
+    let state = load_state(deps.storage)?;
+    check_admin(&info, &state)?;
+    let mut market = load_market(deps.storage, &market_id)?;
+    market.lock = lock;
+    save_market(deps.storage, &market_id, &market)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_lock")
+        .add_attribute("market_id", market_id)
+        .add_attribute("lock", lock.to_string()))
+}
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetState {} => to_binary(&query_state(deps)?),
-        QueryMsg::GetBalance { address } => to_binary(&query_get_account_balance(deps, address)?),
-        QueryMsg::GetRoundPrice { height } => to_binary(&query_get_round_price(deps, height)?),
-        QueryMsg::GetLatestPrice {} => to_binary(&query_get_latest_price(deps)?),
-        QueryMsg::GetHeightBettingList { target_height } => {
-            to_binary(&query_get_height_betting_list(deps, target_height)?)
+        QueryMsg::GetMarket { market_id } => to_binary(&query_get_market(deps, market_id)?),
+        QueryMsg::GetMarkets { start_after, limit } => {
+            to_binary(&query_get_markets(deps, start_after, limit)?)
         }
-        QueryMsg::GetisLock {} => to_binary(&query_state_lock(deps)?),
-        QueryMsg::GetRecentBettingList { target_height } => {
-            to_binary(&query_get_recent_betting_list(deps, target_height)?)
+        QueryMsg::GetDurations { market_id } => to_binary(&query_get_durations(deps, market_id)?),
+        QueryMsg::GetDenoms {} => to_binary(&query_get_denoms(deps)?),
+        QueryMsg::GetCw20Tokens {} => to_binary(&query_get_cw20_tokens(deps)?),
+        QueryMsg::GetBet { id } => to_binary(&query_get_bet(deps, id)?),
+        QueryMsg::BetsByAddress {
+            address,
+            status,
+            start_after,
+            limit,
+        } => to_binary(&query_bets_by_address(
+            deps,
+            address,
+            status,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetBalance { address, denom } => {
+            to_binary(&query_get_account_balance(deps, address, denom)?)
+        }
+        QueryMsg::GetHeldBalance { address, denom } => {
+            to_binary(&query_get_held_balance(deps, address, denom)?)
+        }
+        QueryMsg::GetRoundPrice { market_id, height } => {
+            to_binary(&query_get_round_price(deps, market_id, height)?)
+        }
+        QueryMsg::GetLatestPrice { market_id } => {
+            to_binary(&query_get_latest_price(deps, market_id)?)
+        }
+        QueryMsg::GetHeightBettingList {
+            market_id,
+            target_height,
+        } => to_binary(&query_get_height_betting_list(
+            deps,
+            market_id,
+            target_height,
+        )?),
+        QueryMsg::GetisLock { market_id } => to_binary(&query_state_lock(deps, market_id)?),
+        QueryMsg::GetRecentBettingList {
+            market_id,
+            target_height,
+        } => to_binary(&query_get_recent_betting_list(
+            deps,
+            market_id,
+            target_height,
+        )?),
+        QueryMsg::GetSettlement {
+            market_id,
+            target_height,
+        } => to_binary(&query_get_settlement(deps, market_id, target_height)?),
+        QueryMsg::GetOpenHeights {
+            market_id,
+            start_after,
+            limit,
+        } => to_binary(&query_get_open_heights(
+            deps,
+            market_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetOpenStake { address, denom } => {
+            to_binary(&query_get_open_stake(deps, address, denom)?)
+        }
+        QueryMsg::GetExposure {
+            market_id,
+            target_height,
+            denom,
+        } => to_binary(&query_get_exposure(deps, market_id, target_height, denom)?),
+        QueryMsg::GetExposureLimits { denom } => {
+            to_binary(&query_get_exposure_limits(deps, denom)?)
+        }
+        QueryMsg::GetBankLedger { denom } => to_binary(&query_get_bank_ledger(deps, denom)?),
+        QueryMsg::GetHousePool { denom } => to_binary(&query_get_house_pool(deps, denom)?),
+        QueryMsg::GetLpShares { address, denom } => {
+            to_binary(&query_get_lp_shares(deps, address, denom)?)
+        }
+        QueryMsg::Solvency { denom } => to_binary(&query_solvency(deps, env, denom)?),
+        QueryMsg::GetBankInfo { denom } => to_binary(&query_get_bank_info(deps, env, denom)?),
+        QueryMsg::GetPendingDraws { start_after, limit } => {
+            to_binary(&query_get_pending_draws(deps, start_after, limit)?)
+        }
+        QueryMsg::GetPendingPayBacks { start_after, limit } => {
+            to_binary(&query_get_pending_pay_backs(deps, start_after, limit)?)
+        }
+        QueryMsg::QuoteBet {
+            market_id,
+            position,
+            duration,
+            amount,
+            denom,
+            address,
+        } => to_binary(&query_quote_bet(
+            deps, env, market_id, position, duration, amount, denom, address,
+        )?),
+        QueryMsg::GetOdds {
+            market_id,
+            duration,
+            amount,
+            denom,
+        } => to_binary(&query_get_odds(
+            deps, env, market_id, duration, amount, denom,
+        )?),
+        QueryMsg::PreviewSettlement {
+            market_id,
+            height,
+            price,
+        } => to_binary(&query_preview_settlement(deps, market_id, height, price)?),
+        QueryMsg::GetRoundResult { market_id, height } => {
+            to_binary(&query_get_round_result(deps, market_id, height)?)
+        }
+        QueryMsg::ListRoundResults {
+            market_id,
+            start_after,
+            limit,
+        } => to_binary(&query_list_round_results(
+            deps,
+            market_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetFeeders {} => to_binary(&query_get_feeders(deps)?),
+        QueryMsg::GetFeedRound { market_id, round } => {
+            to_binary(&query_get_feed_round(deps, market_id, round)?)
         }
     }
 }
@@ -334,49 +1619,624 @@ fn query_state(deps: Deps) -> StdResult<State> {
     Ok(state)
 }
 
-fn query_get_account_balance(deps: Deps, address: String) -> StdResult<u128> {
+fn query_get_market(deps: Deps, market_id: String) -> StdResult<MarketResponse> {
+    let market = load_market(deps.storage, &market_id)?;
+    Ok(MarketResponse { market_id, market })
+}
+
+fn query_get_markets(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<MarketResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    MARKETS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(market_id, market)| MarketResponse { market_id, market }))
+        .collect()
+}
+
+fn query_get_durations(deps: Deps, market_id: String) -> StdResult<Vec<DurationResponse>> {
+    DURATIONS
+        .prefix(&market_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(duration, config)| DurationResponse { duration, config }))
+        .collect()
+}
+
+fn query_get_denoms(deps: Deps) -> StdResult<Vec<DenomResponse>> {
+    DENOMS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(denom, config)| DenomResponse {
+                denom,
+                minimum_amount: config.minimum_amount,
+                maximum_amount: config.maximum_amount,
+            })
+        })
+        .collect()
+}
+
+fn query_get_cw20_tokens(deps: Deps) -> StdResult<Vec<DenomResponse>> {
+    CW20_TOKENS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(token, config)| DenomResponse {
+                denom: token.to_string(),
+                minimum_amount: config.minimum_amount,
+                maximum_amount: config.maximum_amount,
+            })
+        })
+        .collect()
+}
+
+//claimable now, held payouts that can be released included
+fn query_get_account_balance(deps: Deps, address: String, denom: Denom) -> StdResult<u128> {
     let addr = deps.api.addr_validate(address.as_str())?;
-    let balance = BALANCE.load(deps.storage, &addr);
-    match balance {
-        Ok(balance) => Ok(balance.into()),
-        Err(_) => Ok(0),
-    }
+    let denom = denom_key(&denom);
+    let balance = BALANCE
+        .may_load(deps.storage, (&addr, &denom))?
+        .unwrap_or_default();
+    let releasable = releasable_balance(deps.storage, &addr, &denom)?
+        .into_iter()
+        .fold(Uint128::zero(), |total, (_, amount)| total + amount);
+    Ok((balance + releasable).into())
 }
 
-fn query_get_round_price(deps: Deps, height: u64) -> StdResult<String> {
-    let price = PRICES.load(deps.storage, height)?;
-    Ok(price.to_string())
+//payouts still waiting for their height to be settled or for its draw
+fn query_get_held_balance(deps: Deps, address: String, denom: Denom) -> StdResult<Uint128> {
+    let addr = deps.api.addr_validate(address.as_str())?;
+    let denom = denom_key(&denom);
+    let held = HELD_BALANCE
+        .prefix((&addr, &denom))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, amount)| amount))
+        .sum::<StdResult<Uint128>>()?;
+    let releasable = releasable_balance(deps.storage, &addr, &denom)?
+        .into_iter()
+        .fold(Uint128::zero(), |total, (_, amount)| total + amount);
+    Ok(held - releasable)
 }
 
-fn query_get_latest_price(deps: Deps) -> StdResult<Uint128> {
-    let state = load_state(deps.storage)?;
+fn query_get_round_price(deps: Deps, market_id: String, height: u64) -> StdResult<PriceResponse> {
+    let market = load_market(deps.storage, &market_id)?;
+    let price = PRICES.load(deps.storage, (&market_id, height))?;
+    Ok(PriceResponse {
+        price: from_price_units(price, market.price_precision)?,
+        precision: market.price_precision,
+        height,
+    })
+}
 
-    Ok(state.latest_price)
+fn query_get_latest_price(deps: Deps, market_id: String) -> StdResult<PriceResponse> {
+    let market = load_market(deps.storage, &market_id)?;
+
+    Ok(PriceResponse {
+        price: from_price_units(market.latest_price, market.price_precision)?,
+        precision: market.price_precision,
+        height: market.latest_price_height,
+    })
 }
 
-fn query_get_height_betting_list(deps: Deps, target_height: u64) -> StdResult<Vec<Betting>> {
-    let bettings = BETTINGS.load(deps.storage, target_height);
-    match bettings {
-        Ok(bettings) => Ok(bettings),
-        Err(_) => Ok(vec![]),
-    }
+fn query_get_bet(deps: Deps, id: u64) -> StdResult<Betting> {
+    bettings().load(deps.storage, id)
 }
 
-fn query_get_recent_betting_list(deps: Deps, target_height: u64) -> StdResult<Vec<Betting>> {
+fn query_bets_by_address(
+    deps: Deps,
+    address: String,
+    status: Option<BetStatus>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Betting>> {
+    let addr = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    bettings()
+        .idx
+        .address
+        .prefix(addr)
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(_, betting)| betting))
+        .filter(|item| match (item, &status) {
+            (Ok(betting), Some(status)) => &betting.status == status,
+            _ => true,
+        })
+        .take(limit)
+        .collect()
+}
+
+fn query_get_height_betting_list(
+    deps: Deps,
+    market_id: String,
+    target_height: u64,
+) -> StdResult<Vec<Betting>> {
+    height_bettings(deps.storage, &market_id, target_height, None, None, None)
+}
+
+fn query_get_recent_betting_list(
+    deps: Deps,
+    market_id: String,
+    target_height: u64,
+) -> StdResult<Vec<Betting>> {
     let mut bettings = vec![];
     for i in 0..=5 {
-        let betting = BETTINGS
-            .load(deps.storage, target_height - i)
-            .unwrap_or_else(|_| vec![]);
+        let betting = height_bettings(
+            deps.storage,
+            &market_id,
+            target_height - i,
+            None,
+            None,
+            None,
+        )?;
         bettings.push(betting)
     }
     let bettings = bettings.into_iter().flatten().collect::<Vec<Betting>>();
     Ok(bettings)
 }
-fn query_state_lock(deps: Deps) -> StdResult<bool> {
-    let state = load_state(deps.storage)?;
-    match state.lock {
+fn query_state_lock(deps: Deps, market_id: String) -> StdResult<bool> {
+    let market = load_market(deps.storage, &market_id)?;
+    match market.lock {
         true => Ok(true),
         false => Ok(false),
     }
 }
+
+fn query_get_settlement(
+    deps: Deps,
+    market_id: String,
+    target_height: u64,
+) -> StdResult<SettlementResponse> {
+    let key = (market_id.as_str(), target_height);
+    let settled_index = match SETTLE_CURSOR.may_load(deps.storage)? {
+        Some(cursor) if cursor.market_id == market_id && cursor.height == target_height => {
+            Some(cursor.index)
+        }
+        _ => None,
+    };
+    Ok(SettlementResponse {
+        queued: SETTLE_QUEUE.has(deps.storage, key),
+        settled_index,
+        settled_at: SETTLED_HEIGHTS.may_load(deps.storage, key)?,
+        market_id,
+        target_height,
+    })
+}
+
+fn query_get_open_heights(
+    deps: Deps,
+    market_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<u64>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    OPEN_HEIGHTS
+        .prefix(&market_id)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
+
+fn query_get_open_stake(deps: Deps, address: String, denom: Denom) -> StdResult<Uint128> {
+    let addr = deps.api.addr_validate(&address)?;
+    let open_stake = OPEN_STAKE
+        .may_load(deps.storage, (&addr, &denom_key(&denom)))?
+        .unwrap_or_default();
+    Ok(open_stake)
+}
+
+fn query_get_exposure(
+    deps: Deps,
+    market_id: String,
+    target_height: u64,
+    denom: Denom,
+) -> StdResult<Exposure> {
+    let exposure = EXPOSURE
+        .may_load(
+            deps.storage,
+            (&market_id, target_height, &denom_key(&denom)),
+        )?
+        .unwrap_or_default();
+    Ok(exposure)
+}
+
+fn query_get_exposure_limits(deps: Deps, denom: Denom) -> StdResult<ExposureLimits> {
+    let limits = EXPOSURE_LIMITS
+        .may_load(deps.storage, &denom_key(&denom))?
+        .unwrap_or_default();
+    Ok(limits)
+}
+
+fn query_get_bank_ledger(deps: Deps, denom: Denom) -> StdResult<BankLedger> {
+    let ledger = BANK_LEDGER
+        .may_load(deps.storage, &denom_key(&denom))?
+        .unwrap_or_default();
+    Ok(ledger)
+}
+
+fn query_get_house_pool(deps: Deps, denom: Denom) -> StdResult<HousePoolResponse> {
+    let key = denom_key(&denom);
+    let pool = HOUSE_POOLS
+        .may_load(deps.storage, &key)?
+        .unwrap_or_default();
+    let ledger = BANK_LEDGER
+        .may_load(deps.storage, &key)?
+        .unwrap_or_default();
+    Ok(HousePoolResponse {
+        total_shares: pool.total_shares,
+        value: pool.value,
+        reserved: ledger.reserved,
+        debt: ledger.pending_draw,
+        free: pool
+            .value
+            .saturating_sub(ledger.reserved + ledger.pending_draw),
+    })
+}
+
+fn query_get_lp_shares(deps: Deps, address: String, denom: Denom) -> StdResult<LpSharesResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let key = denom_key(&denom);
+    let shares = LP_SHARES
+        .may_load(deps.storage, (&addr, &key))?
+        .unwrap_or_default();
+    let pool = HOUSE_POOLS
+        .may_load(deps.storage, &key)?
+        .unwrap_or_default();
+    let ledger = BANK_LEDGER
+        .may_load(deps.storage, &key)?
+        .unwrap_or_default();
+    let free = pool
+        .value
+        .saturating_sub(ledger.reserved + ledger.pending_draw);
+    Ok(LpSharesResponse {
+        shares,
+        value: pool.value_of(shares, ledger.pending_draw),
+        withdrawable: pool.free_value_of(shares, free),
+    })
+}
+
+fn query_solvency(deps: Deps, env: Env, denom: Denom) -> StdResult<SolvencyResponse> {
+    let state = load_state(deps.storage)?;
+    let ledger = BANK_LEDGER
+        .may_load(deps.storage, &denom_key(&denom))?
+        .unwrap_or_default();
+    let house = state.pool_mode == (PoolMode::House {});
+    let expected_balance = match house {
+        true => {
+            let pool = HOUSE_POOLS
+                .may_load(deps.storage, &denom_key(&denom))?
+                .unwrap_or_default();
+            ledger.held() + pool.value
+        }
+        false => ledger.held(),
+    };
+    let actual_balance = query_denom_balance(&deps.querier, &env.contract.address, &denom)?;
+    //an unreachable bank is reported, not an error
+    let bank = match house {
+        true => None,
+        false => query_borrowed(
+            &deps.querier,
+            &state.bank_contract,
+            &env.contract.address,
+            &denom,
+        )
+        .ok(),
+    };
+
+    let mut discrepancies = vec![];
+    match actual_balance.cmp(&expected_balance) {
+        Less => discrepancies.push(format!(
+            "balance is short by {}",
+            expected_balance - actual_balance
+        )),
+        Greater => discrepancies.push(format!(
+            "balance has {} not accounted for",
+            actual_balance - expected_balance
+        )),
+        Equal => {}
+    }
+    match &bank {
+        Some(bank) => {
+            if bank.borrowed != ledger.drawn {
+                discrepancies.push(format!(
+                    "bank records {} borrowed, ledger has {} drawn",
+                    bank.borrowed, ledger.drawn
+                ))
+            }
+            if bank.repaid != ledger.paid_back {
+                discrepancies.push(format!(
+                    "bank records {} repaid, ledger has {} paid back",
+                    bank.repaid, ledger.paid_back
+                ))
+            }
+        }
+        None if !house => discrepancies.push("bank did not answer the borrowed query".to_string()),
+        None => {}
+    }
+
+    Ok(SolvencyResponse {
+        denom,
+        total_borrowed: ledger.drawn,
+        total_repaid: ledger.paid_back,
+        total_outstanding: ledger.net_borrowed(),
+        reserved: ledger.reserved,
+        pending_pay_back: ledger.pending_pay_back,
+        pending_draw: ledger.pending_draw,
+        expected_balance,
+        actual_balance,
+        bank_borrowed: bank.as_ref().map(|bank| bank.borrowed),
+        bank_repaid: bank.as_ref().map(|bank| bank.repaid),
+        solvent: actual_balance >= expected_balance,
+        discrepancies,
+    })
+}
+
+fn query_get_bank_info(deps: Deps, env: Env, denom: Denom) -> StdResult<BankInfoResponse> {
+    let state = load_state(deps.storage)?;
+    let bank = state.bank_contract;
+    let to_std_err = |err: ContractError| StdError::generic_err(err.to_string());
+    Ok(BankInfoResponse {
+        version: query_bank_version(&deps.querier, &bank).map_err(to_std_err)?,
+        liquidity: query_liquidity(&deps.querier, &bank, &denom).map_err(to_std_err)?,
+        borrowed: query_borrowed(&deps.querier, &bank, &env.contract.address, &denom)
+            .map_err(to_std_err)?,
+        lp_token: query_lp_token_info(&deps.querier, &bank).map_err(to_std_err)?,
+        contract: bank,
+    })
+}
+
+fn query_get_pending_pay_backs(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PendingPayBackResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    PAY_BACKS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, pay_back)| PendingPayBackResponse { id, pay_back }))
+        .collect()
+}
+
+fn query_get_pending_draws(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PendingDrawResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    DRAWS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, draw)| PendingDrawResponse { id, draw }))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn query_quote_bet(
+    deps: Deps,
+    env: Env,
+    market_id: String,
+    position: String,
+    duration: u64,
+    amount: Uint128,
+    denom: Denom,
+    address: Option<String>,
+) -> StdResult<QuoteResponse> {
+    let bettor = match address {
+        Some(address) => Some(deps.api.addr_validate(&address)?),
+        None => None,
+    };
+    let prepared = prepare_bet(
+        deps,
+        &env,
+        bettor.as_ref(),
+        &denom,
+        amount,
+        &market_id,
+        &position,
+        duration,
+    );
+    let quote = match prepared {
+        Ok(prepared) => {
+            let market = load_market(deps.storage, &market_id)?;
+            QuoteResponse {
+                target_height: prepared.target_height,
+                base_price: Some(from_price_units(
+                    prepared.base_price,
+                    market.price_precision,
+                )?),
+                win_amount: Some(prepared.win_amount),
+                error: None,
+            }
+        }
+        Err(err) => QuoteResponse {
+            target_height: env.block.height + duration,
+            base_price: None,
+            win_amount: None,
+            error: Some(err.to_string()),
+        },
+    };
+    Ok(quote)
+}
+
+fn query_get_odds(
+    deps: Deps,
+    env: Env,
+    market_id: String,
+    duration: u64,
+    amount: Uint128,
+    denom: Denom,
+) -> StdResult<OddsResponse> {
+    let state = load_state(deps.storage)?;
+    let config = DURATIONS.load(deps.storage, (&market_id, duration))?;
+    let target_height = env.block.height + duration;
+    let exposure = EXPOSURE
+        .may_load(
+            deps.storage,
+            (&market_id, target_height, &denom_key(&denom)),
+        )?
+        .unwrap_or_default();
+
+    //win_amount = betting_amount * (1 + payout_rate), priced after the betting like prepare_bet
+    let odds = |position: Position| {
+        let mut new_exposure = exposure.clone();
+        new_exposure.add(&position, amount);
+        Decimal::one() + payout_rate(&state.odds_mode, config.fee_late, &new_exposure, &position)
+    };
+    Ok(OddsResponse {
+        target_height,
+        long: odds(Position::Long),
+        short: odds(Position::Short),
+        equal: odds(Position::Eqaul),
+    })
+}
+
+fn query_preview_settlement(
+    deps: Deps,
+    market_id: String,
+    height: u64,
+    price: Decimal,
+) -> StdResult<PreviewSettlementResponse> {
+    let market = load_market(deps.storage, &market_id)?;
+    let price = to_price_units(price, market.price_precision)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let task = SettleTask::Round { price };
+
+    //bettings already paid are not open anymore
+    let bettings = height_bettings(
+        deps.storage,
+        &market_id,
+        height,
+        Some(&BetStatus::Open),
+        None,
+        None,
+    )?;
+
+    let mut winners = 0;
+    let mut losers = 0;
+    let mut payouts = vec![];
+    let mut pay_back = vec![];
+    let mut draw = vec![];
+    let mut accounts: Vec<AccountPayout> = vec![];
+    for betting in bettings.iter() {
+        let settlement = settle_betting(betting, &task);
+        add_denom_amount(&mut pay_back, &betting.denom, settlement.pay_back);
+        add_denom_amount(&mut draw, &betting.denom, settlement.draw);
+        if settlement.status != BetStatus::Won {
+            losers += 1;
+            continue;
+        }
+        winners += 1;
+        add_denom_amount(&mut payouts, &betting.denom, settlement.payout);
+        match accounts
+            .iter_mut()
+            .find(|account| account.address == betting.address && account.denom == betting.denom)
+        {
+            Some(account) => account.amount += settlement.payout,
+            None => accounts.push(AccountPayout {
+                address: betting.address.clone(),
+                denom: betting.denom.clone(),
+                amount: settlement.payout,
+            }),
+        }
+    }
+
+    Ok(PreviewSettlementResponse {
+        market_id,
+        height,
+        winners,
+        losers,
+        payouts,
+        pay_back,
+        draw,
+        accounts,
+    })
+}
+
+fn query_get_round_result(
+    deps: Deps,
+    market_id: String,
+    height: u64,
+) -> StdResult<RoundResultResponse> {
+    let result = ROUND_RESULTS.load(deps.storage, (&market_id, height))?;
+    Ok(RoundResultResponse {
+        market_id,
+        height,
+        result,
+    })
+}
+
+fn query_list_round_results(
+    deps: Deps,
+    market_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<RoundResultResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    ROUND_RESULTS
+        .prefix(&market_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(height, result)| RoundResultResponse {
+                market_id: market_id.clone(),
+                height,
+                result,
+            })
+        })
+        .collect()
+}
+
+fn query_get_feeders(deps: Deps) -> StdResult<Vec<Addr>> {
+    FEEDERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect()
+}
+
+fn query_get_feed_round(
+    deps: Deps,
+    market_id: String,
+    round: Option<u64>,
+) -> StdResult<FeedRoundResponse> {
+    let market = load_market(deps.storage, &market_id)?;
+    let round = match round {
+        Some(round) => round,
+        None => FEED_ROUND.may_load(deps.storage, &market_id)?.unwrap_or(1),
+    };
+    let price = match FEED_ROUNDS.may_load(deps.storage, (&market_id, round))? {
+        Some(feed_round) => Some(PriceResponse {
+            price: from_price_units(feed_round.price, market.price_precision)?,
+            precision: market.price_precision,
+            height: feed_round.height,
+        }),
+        None => None,
+    };
+    let submissions = FEEDER_SUBMISSIONS
+        .prefix((&market_id, round))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (feeder, submission) = item?;
+            Ok(FeederSubmissionResponse {
+                feeder,
+                price: from_price_units(submission.price, market.price_precision)?,
+                height: submission.height,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(FeedRoundResponse {
+        market_id,
+        round,
+        price,
+        submissions,
+    })
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -30,6 +30,12 @@ pub enum ContractError {
     #[error("Invalid duration")]
     InvalidDuration {},
 
+    #[error("Maximum Amount Invalid")]
+    InvalidMaximumAmount {},
+
+    #[error("Fee late must not be more than 100")]
+    InvalidFeeLate {},
+
     #[error("Invalid Height")]
     InvalidHeight {},
 
@@ -39,15 +45,120 @@ pub enum ContractError {
     #[error("pool balance must not be less than the options being played. ")]
     InvalidWithdrawBalance {},
 
+    #[error("Only in house pool mode")]
+    NotHouseMode {},
+
+    #[error("Shares must be between 1 and the owned shares")]
+    InvalidShares {},
+
     #[error("Expires is Invalid you must setting expires")]
     InvalidExpires {},
 
+    #[error("Not a compatible bank contract: {reason}")]
+    InvalidBankContract { reason: String },
+
+    #[error("Bank contract query failed: {reason}")]
+    BankQueryFailed { reason: String },
+
+    #[error("No PayBack to retry")]
+    NoPayBackToRetry {},
+
+    #[error("No draw to retry")]
+    NoDrawToRetry {},
+
+    #[error("Bank contract still has pending transfers or reserved amounts")]
+    BankInUse {},
+
     #[error("This is more than the current pool can handle.")]
     NotEnoughPool {},
 
+    #[error("House pool owes more than it holds, deposits are closed until the draws are covered")]
+    PoolInDebt {},
+
+    #[error("Reserved amount would be more than the credit line {credit_line}")]
+    ExceedCreditLine { credit_line: Uint128 },
+
+    #[error("Pool share must be between 1 and 10000 bps")]
+    InvalidPoolShare {},
+
+    #[error("Betting amount is more than {max_bet_amount}")]
+    ExceedMaxBetAmount { max_bet_amount: Uint128 },
+
+    #[error("Open stake of the address would be more than {max_open_stake}")]
+    ExceedMaxOpenStake { max_open_stake: Uint128 },
+
+    #[error("Net exposure of the target height would be more than {max_net_exposure}")]
+    ExceedMaxNetExposure { max_net_exposure: Uint128 },
+
+    #[error("Invalid odds bounds")]
+    InvalidOddsBounds {},
+
     #[error("You placed a bet over the lock height.")]
     OverDeadline {},
 
     #[error("Contract is lock")]
     Lock {},
+
+    #[error("No balance to claim")]
+    NoBalance {},
+
+    #[error("Claim amount must be between 1 and the claimable balance")]
+    InvalidClaimAmount {},
+
+    #[error("Settle limit must be greater than 0")]
+    InvalidSettleLimit {},
+
+    #[error("Refund is available from height {expire_height}")]
+    RefundNotExpired { expire_height: u64 },
+
+    #[error("Target height has a round price or is already settled")]
+    RefundNotAvailable {},
+
+    #[error("No betting to refund")]
+    NoBettingToRefund {},
+
+    #[error("Prices come from the oracle contract")]
+    OracleMode {},
+
+    #[error("No oracle contract is set")]
+    NotOracleMode {},
+
+    #[error("Prices come from the registered feeders")]
+    FeederMode {},
+
+    #[error("Prices do not come from feeders")]
+    NotFeederMode {},
+
+    #[error("Only Feeder")]
+    OnlyFeeder {},
+
+    #[error("Quorum must be between 1 and the number of feeders")]
+    InvalidQuorum {},
+
+    #[error("Price moved {deviation_bps} bps, more than {max_deviation_bps} bps")]
+    PriceDeviation {
+        deviation_bps: String,
+        max_deviation_bps: u64,
+    },
+
+    #[error("Market has no price yet")]
+    NoMarketPrice {},
+
+    #[error("Price must be greater than zero")]
+    ZeroPrice {},
+
+    #[error("Latest price at height {latest_price_height} is stale")]
+    StalePrice { latest_price_height: u64 },
+
+    #[error("Oracle price at height {height} is stale")]
+    StaleOraclePrice { height: u64 },
+
+    #[error("Price must have at most {precision} decimal places")]
+    InvalidPricePrecision { precision: u32 },
+
+    #[error("Market {market_id} already exists")]
+    MarketExists { market_id: String },
+
+    #[error("Invalid market id")]
+    InvalidMarketId {},
 }
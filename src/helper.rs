@@ -1,7 +1,16 @@
+use crate::bank::query_liquidity;
 use crate::error::ContractError;
-use crate::state::State;
+use crate::state::{
+    denom_key, BankLedger, DenomAmount, DenomConfig, DurationConfig, Exposure, ExposureLimits,
+    Market, OddsMode, PoolMode, Position, State, CW20_TOKENS, DENOMS, DURATIONS, HOUSE_POOLS,
+};
 
-use cosmwasm_std::MessageInfo;
+use cosmwasm_std::{
+    coin, to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, MessageInfo, QuerierWrapper,
+    StdError, StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Denom};
+use std::cmp::Ordering::*;
 
 pub fn check_admin(info: &MessageInfo, state: &State) -> Result<(), ContractError> {
     // match state.admin == info.sender {
@@ -13,30 +22,55 @@ pub fn check_admin(info: &MessageInfo, state: &State) -> Result<(), ContractErro
         false => Err(ContractError::OnlyOwner {}),
     }
 }
-pub fn check_lock(state: &State) -> Result<(), ContractError> {
-    match state.lock {
+pub fn check_lock(market: &Market) -> Result<(), ContractError> {
+    match market.lock {
         true => Err(ContractError::Lock {}),
         false => Ok(()),
     }
 }
-pub fn check_denom(info: &MessageInfo, state: &State) -> Result<(), ContractError> {
+pub fn check_funds(info: &MessageInfo) -> Result<Coin, ContractError> {
     //token check
     match info.funds.len() {
         0 => Err(ContractError::MustSendCoin {}),
-        1 => Ok(()),
+        1 => Ok(info.funds[0].clone()),
         _ => Err(ContractError::InvalidOneTypeCoin {}),
-    }?;
+    }
+}
 
-    let coin = &info.funds[0];
-    //denom_check
-    match coin.denom == state.denom {
-        true => Ok(()),
-        false => Err(ContractError::InvalidDenom {}),
+pub fn check_denom(
+    storage: &dyn Storage,
+    denom: &Denom,
+    amount: Uint128,
+) -> Result<DenomConfig, ContractError> {
+    //denom_check, cw20 only from whitelisted contracts
+    let config = match denom {
+        Denom::Native(denom) => DENOMS.may_load(storage, denom)?,
+        Denom::Cw20(token) => CW20_TOKENS.may_load(storage, token)?,
+    };
+    let config = match config {
+        Some(config) => Ok(config),
+        None => Err(ContractError::InvalidDenom {}),
     }?;
 
-    match coin.amount >= state.minimum_amount {
+    match amount >= config.minimum_amount {
         true => Ok(()),
         false => Err(ContractError::InvalidMinimumAmount {}),
+    }?;
+
+    match config.maximum_amount {
+        Some(maximum_amount) if amount > maximum_amount => {
+            Err(ContractError::InvalidMaximumAmount {})
+        }
+        _ => Ok(config),
+    }
+}
+
+pub fn check_denom_config(config: &DenomConfig) -> Result<(), ContractError> {
+    match config.maximum_amount {
+        Some(maximum_amount) if maximum_amount < config.minimum_amount => {
+            Err(ContractError::InvalidMaximumAmount {})
+        }
+        _ => Ok(()),
     }
 }
 
@@ -49,10 +83,332 @@ pub fn check_denom(info: &MessageInfo, state: &State) -> Result<(), ContractErro
 // }
 
 //block_height + 1 = 6s
-pub fn check_duration(duration: u64) -> Result<(), ContractError> {
-    match duration {
-        30 | 50 => Ok(()),
-        _ => Err(ContractError::InvalidDuration {}),
+pub fn check_duration(
+    storage: &dyn Storage,
+    market_id: &str,
+    duration: u64,
+) -> Result<DurationConfig, ContractError> {
+    match DURATIONS.may_load(storage, (market_id, duration))? {
+        Some(config) => Ok(config),
+        None => Err(ContractError::InvalidDuration {}),
+    }
+}
+
+pub fn check_fee_late(fee_late: u8) -> Result<(), ContractError> {
+    match fee_late <= 100 {
+        true => Ok(()),
+        false => Err(ContractError::InvalidFeeLate {}),
+    }
+}
+
+/*
+A bet placed at h is priced at h+1 by the Setting at h, so a 1 block duration
+would target a price already on chain and 0 the base price itself.
+ */
+const MIN_DURATION: u64 = 2;
+
+pub fn check_duration_length(duration: u64) -> Result<(), ContractError> {
+    match duration >= MIN_DURATION {
+        true => Ok(()),
+        false => Err(ContractError::InvalidDuration {}),
+    }
+}
+
+//decimal places of Decimal
+const MAX_PRICE_PRECISION: u32 = 18;
+
+pub fn check_price_precision(precision: u32) -> Result<(), ContractError> {
+    match precision <= MAX_PRICE_PRECISION {
+        true => Ok(()),
+        false => Err(ContractError::InvalidPricePrecision { precision }),
+    }
+}
+
+/*
+Prices are stored as integers scaled by 10^price_precision.
+price_precision = 5 : 1.00001 => 100001
+ */
+pub fn to_price_units(price: Decimal, precision: u32) -> Result<Uint128, ContractError> {
+    check_price_precision(precision)?;
+    let scale = Uint128::new(10).pow(MAX_PRICE_PRECISION - precision);
+    let atomics = price.atomics();
+    match atomics.checked_rem(scale) {
+        Ok(rem) if rem.is_zero() => Ok(atomics / scale),
+        _ => Err(ContractError::InvalidPricePrecision { precision }),
+    }
+}
+
+//oracle and feeder prices come with up to 18 decimals, rounded half up to the market precision
+pub fn round_price_units(price: Decimal, precision: u32) -> Result<Uint128, ContractError> {
+    check_price_precision(precision)?;
+    let scale = Uint128::new(10).pow(MAX_PRICE_PRECISION - precision);
+    let half = scale / Uint128::new(2);
+    let atomics = price.atomics();
+    let units = atomics / scale;
+    match !half.is_zero() && atomics % scale >= half {
+        true => Ok(units + Uint128::one()),
+        false => Ok(units),
+    }
+}
+
+pub fn from_price_units(price: Uint128, precision: u32) -> StdResult<Decimal> {
+    Decimal::from_atomics(price, precision).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+pub fn transfer_msg(to: &Addr, denom: &Denom, amount: Uint128) -> StdResult<CosmosMsg> {
+    let msg = match denom {
+        Denom::Native(denom) => CosmosMsg::Bank(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![coin(amount.u128(), denom)],
+        }),
+        Denom::Cw20(token) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }),
+    };
+    Ok(msg)
+}
+
+pub fn query_denom_balance(
+    querier: &QuerierWrapper,
+    address: &Addr,
+    denom: &Denom,
+) -> StdResult<Uint128> {
+    match denom {
+        Denom::Native(denom) => Ok(querier.query_balance(address, denom)?.amount),
+        Denom::Cw20(token) => {
+            let balance: BalanceResponse = querier.query_wasm_smart(
+                token,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )?;
+            Ok(balance.balance)
+        }
+    }
+}
+
+/*
+Reject a betting the pool may not be able to cover.
+free liquidity = available - borrowed - reserved with the bank, house pool value - reserved without,
+and the borrow amount (the pool's part of the payout) must stay within max_pool_share_bps of it.
+ */
+pub fn check_pool(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+    state: &State,
+    denom: &Denom,
+    ledger: &BankLedger,
+    borrow_amount: Uint128,
+) -> Result<(), ContractError> {
+    let free_liquidity = match state.pool_mode {
+        PoolMode::Bank {} => {
+            let liquidity = query_liquidity(querier, &state.bank_contract, denom)?;
+            liquidity
+                .available
+                .saturating_sub(liquidity.borrowed)
+                .saturating_sub(ledger.reserved)
+        }
+        PoolMode::House {} => HOUSE_POOLS
+            .may_load(storage, &denom_key(denom))?
+            .unwrap_or_default()
+            .value
+            .saturating_sub(ledger.reserved + ledger.pending_draw),
+    };
+    let max_borrow = free_liquidity.multiply_ratio(state.max_pool_share_bps, 10000u128);
+
+    match borrow_amount <= max_borrow {
+        true => Ok(()),
+        false => Err(ContractError::NotEnoughPool {}),
+    }
+}
+
+pub fn check_house_mode(state: &State) -> Result<(), ContractError> {
+    match state.pool_mode {
+        PoolMode::House {} => Ok(()),
+        PoolMode::Bank {} => Err(ContractError::NotHouseMode {}),
+    }
+}
+
+pub fn check_credit_line(ledger: &BankLedger, borrow_amount: Uint128) -> Result<(), ContractError> {
+    match ledger.credit_line {
+        Some(credit_line) if ledger.reserved + borrow_amount > credit_line => {
+            Err(ContractError::ExceedCreditLine { credit_line })
+        }
+        _ => Ok(()),
+    }
+}
+
+//open_stake and exposure already include the new betting
+pub fn check_exposure(
+    limits: &ExposureLimits,
+    amount: Uint128,
+    open_stake: Uint128,
+    before: &Exposure,
+    after: &Exposure,
+) -> Result<(), ContractError> {
+    match limits.max_bet_amount {
+        Some(max_bet_amount) if amount > max_bet_amount => {
+            Err(ContractError::ExceedMaxBetAmount { max_bet_amount })
+        }
+        _ => Ok(()),
+    }?;
+
+    match limits.max_open_stake {
+        Some(max_open_stake) if open_stake > max_open_stake => {
+            Err(ContractError::ExceedMaxOpenStake { max_open_stake })
+        }
+        _ => Ok(()),
+    }?;
+
+    //a betting on the smaller side is always allowed
+    match limits.max_net_exposure {
+        Some(max_net_exposure) if after.net() > max_net_exposure && after.net() > before.net() => {
+            Err(ContractError::ExceedMaxNetExposure { max_net_exposure })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn payout_rate(
+    mode: &OddsMode,
+    fee_late: u8,
+    exposure: &Exposure,
+    position: &Position,
+) -> Decimal {
+    //3/100 = 0.03
+    let fee_late = Decimal::from_ratio(fee_late, Uint128::new(100));
+    let base_rate = Decimal::one() - fee_late;
+
+    let (sensitivity_bps, min_payout_bps, max_payout_bps) = match mode {
+        OddsMode::Fixed {} => return base_rate,
+        OddsMode::Dynamic {
+            sensitivity_bps,
+            min_payout_bps,
+            max_payout_bps,
+        } => (*sensitivity_bps, *min_payout_bps, *max_payout_bps),
+    };
+
+    let (same, other) = match position {
+        Position::Long => (exposure.long, exposure.short),
+        Position::Short => (exposure.short, exposure.long),
+        //equal is not a direction
+        Position::Eqaul => (Uint128::zero(), Uint128::zero()),
+    };
+    let total = same + other;
+    let rate = match total.is_zero() {
+        true => base_rate,
+        false => match same >= other {
+            //crowded side
+            true => {
+                let skew = Decimal::from_ratio(same - other, total);
+                let adjust = skew * Decimal::from_ratio(sensitivity_bps, 10000u128);
+                match base_rate > adjust {
+                    true => base_rate - adjust,
+                    false => Decimal::zero(),
+                }
+            }
+            false => {
+                let skew = Decimal::from_ratio(other - same, total);
+                base_rate + skew * Decimal::from_ratio(sensitivity_bps, 10000u128)
+            }
+        },
+    };
+
+    let min_rate = Decimal::from_ratio(min_payout_bps, 10000u128);
+    let max_rate = Decimal::from_ratio(max_payout_bps, 10000u128);
+    rate.max(min_rate).min(max_rate)
+}
+
+pub fn check_odds_mode(mode: &OddsMode) -> Result<(), ContractError> {
+    match mode {
+        OddsMode::Dynamic {
+            min_payout_bps,
+            max_payout_bps,
+            ..
+        } if min_payout_bps > max_payout_bps => Err(ContractError::InvalidOddsBounds {}),
+        _ => Ok(()),
+    }
+}
+
+pub fn check_pool_share(bps: u64) -> Result<(), ContractError> {
+    match bps > 0 && bps <= 10000 {
+        true => Ok(()),
+        false => Err(ContractError::InvalidPoolShare {}),
+    }
+}
+
+pub fn add_denom_amount(amounts: &mut Vec<DenomAmount>, denom: &Denom, amount: Uint128) {
+    match amounts.iter_mut().find(|exsists| &exsists.denom == denom) {
+        Some(total) => total.amount += amount,
+        None => amounts.push(DenomAmount {
+            denom: denom.clone(),
+            amount,
+        }),
+    }
+}
+
+//latest_price is too old to be trusted
+pub fn check_price_gap(state: &State, market: &Market, height: u64) -> Result<(), ContractError> {
+    //a market added without a price has latest_price 0 until its first Setting
+    if market.latest_price_height == 0 {
+        return Err(ContractError::NoMarketPrice {});
+    }
+    match state.price_guard.max_block_gap {
+        Some(max_block_gap)
+            if height.saturating_sub(market.latest_price_height) > max_block_gap =>
+        {
+            Err(ContractError::StalePrice {
+                latest_price_height: market.latest_price_height,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn check_price_guard(
+    state: &State,
+    market: &Market,
+    price: Uint128,
+    height: u64,
+) -> Result<(), ContractError> {
+    //first price, or first price after a gap longer than max_block_gap,
+    //the latest price is too old to compare with
+    if market.latest_price.is_zero() || check_price_gap(state, market, height).is_err() {
+        return Ok(());
+    }
+
+    if let Some(max_deviation_bps) = state.price_guard.max_deviation_bps {
+        let diff = match price.cmp(&market.latest_price) {
+            Less => market.latest_price - price,
+            Equal => return Ok(()),
+            Greater => price - market.latest_price,
+        };
+        let deviation_bps = diff.full_mul(10000u64) / market.latest_price.full_mul(1u64);
+        if deviation_bps > max_deviation_bps.into() {
+            return Err(ContractError::PriceDeviation {
+                deviation_bps: deviation_bps.to_string(),
+                max_deviation_bps,
+            });
+        }
+    }
+    Ok(())
+}
+
+//prices must not be empty
+pub fn median(mut prices: Vec<Uint128>) -> Uint128 {
+    prices.sort();
+    let mid = prices.len() / 2;
+    match prices.len() % 2 {
+        1 => prices[mid],
+        _ => {
+            let (low, high) = (prices[mid - 1], prices[mid]);
+            low + (high - low) / Uint128::new(2)
+        }
     }
 }
 
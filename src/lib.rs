@@ -1,7 +1,11 @@
+pub mod bank;
 pub mod contract;
 mod error;
 pub mod helper;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mock_oracle;
 pub mod msg;
-pub mod query;
+pub mod oracle;
+pub mod settlement;
 pub mod state;
 pub use crate::error::ContractError;
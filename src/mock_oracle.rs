@@ -0,0 +1,57 @@
+//! Minimal oracle speaking `OracleQueryMsg`, for local multi-test setups.
+//! Wrap it with `ContractWrapper::new(execute, instantiate, query)`.
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult,
+};
+use cw_storage_plus::{Item, Map};
+
+use crate::oracle::{OraclePriceResponse, OracleQueryMsg};
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    SetPrice { market_id: String, price: Decimal },
+}
+
+const OWNER: Item<Addr> = Item::new("owner");
+// key - market_id
+const PRICES: Map<&str, OraclePriceResponse> = Map::new("prices");
+
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _msg: InstantiateMsg,
+) -> StdResult<Response> {
+    OWNER.save(deps.storage, &info.sender)?;
+    Ok(Response::new())
+}
+
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+    if OWNER.load(deps.storage)? != info.sender {
+        return Err(StdError::generic_err("Only Onwer"));
+    }
+    match msg {
+        ExecuteMsg::SetPrice { market_id, price } => PRICES.save(
+            deps.storage,
+            market_id.as_str(),
+            &OraclePriceResponse {
+                price,
+                height: env.block.height,
+            },
+        )?,
+    }
+    Ok(Response::new())
+}
+
+pub fn query(deps: Deps, _env: Env, msg: OracleQueryMsg) -> StdResult<Binary> {
+    match msg {
+        OracleQueryMsg::Price { market_id } => {
+            to_binary(&PRICES.load(deps.storage, market_id.as_str())?)
+        }
+    }
+}
@@ -1,40 +1,315 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Uint128;
-use cw20::Expiration;
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw2::ContractVersion;
+use cw20::{Cw20ReceiveMsg, Denom, Expiration};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::bank::{BorrowedResponse, LiquidityResponse, LpTokenInfoResponse};
+use crate::state::{
+    BetStatus, DenomAmount, DurationConfig, ExposureLimits, Market, MissedRoundPolicy, OddsMode,
+    PendingDraw, PendingPayBack, PoolMode, PriceGuard, PriceSource, RoundResult, SignedAmount,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
+    //first accepted denom, more with ExecuteMsg::SetDenom
     pub denom: String,
+    //first market, more with ExecuteMsg::AddMarket
+    pub market_id: String,
     pub fee_late: u8,
-    // pub oracle_address: String, //솔루션 나오면 업데이트
-    pub price: String,
+    pub oracle_address: Option<String>,
+    //latest price of the first market, bets can use it until the first Setting
+    pub price: Decimal,
     pub minimum_amount: u64,
     pub bank_contract_address: String,
+    //decimal places of prices, 5 when None
+    pub price_precision: Option<u32>,
+    pub settle_limit: Option<u64>,
+    pub missed_round_policy: Option<MissedRoundPolicy>,
+    pub refund_timeout: Option<u64>,
+    //10000 (100%) when None
+    pub max_pool_share_bps: Option<u64>,
+    //Bank when None, bank_contract_address is not used in House mode
+    pub pool_mode: Option<PoolMode>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    Betting { position: String, duration: u64 },
-    Setting { price: Uint128, lock: Option<bool> },
-    SetFeeLate { fee_late: u8 },
-    SetMinimumAmount { amount: u64 },
-    SetBankContract { address: String },
-    AddAdmin { address: String },
+    Betting {
+        market_id: String,
+        position: String,
+        duration: u64,
+    },
+    Setting {
+        market_id: String,
+        price: Decimal,
+        lock: Option<bool>,
+    },
+    UpdatePrice {
+        market_id: String,
+    },
+    SubmitPrice {
+        market_id: String,
+        price: Decimal,
+    },
+    Receive(Cw20ReceiveMsg),
+    //house pool mode, mints LP shares for the sent coin
+    Deposit {},
+    //house pool mode, burns shares for their part of the pool value
+    Withdraw {
+        denom: Denom,
+        shares: Uint128,
+    },
+    Claim {
+        denom: Denom,
+        amount: Option<Uint128>,
+    },
+    Settle {
+        limit: u64,
+    },
+    //send PayBacks the bank rejected again, lowest id first
+    RetryPayBack {
+        limit: Option<u32>,
+    },
+    //send BorrowBalances the bank rejected again, lowest id first,
+    //in house pool mode cover the draws from the free liquidity of the pool
+    RetryDraw {
+        limit: Option<u32>,
+    },
+    RefundExpired {
+        market_id: String,
+        target_height: u64,
+    },
+    SetSettleLimit {
+        limit: u64,
+    },
+    SetMissedRoundPolicy {
+        policy: MissedRoundPolicy,
+    },
+    SetRefundTimeout {
+        blocks: u64,
+    },
+    SetMaxPoolShare {
+        bps: u64,
+    },
+    AddMarket {
+        market_id: String,
+        fee_late: u8,
+        durations: Vec<u64>,
+        price_precision: Option<u32>,
+    },
+    SetDuration {
+        market_id: String,
+        duration: u64,
+        fee_late: u8,
+    },
+    RemoveDuration {
+        market_id: String,
+        duration: u64,
+    },
+    SetDenom {
+        denom: String,
+        minimum_amount: u64,
+        maximum_amount: Option<u64>,
+    },
+    RemoveDenom {
+        denom: String,
+    },
+    SetCw20Token {
+        address: String,
+        minimum_amount: u64,
+        maximum_amount: Option<u64>,
+    },
+    RemoveCw20Token {
+        address: String,
+    },
+    SetBankContract {
+        address: String,
+    },
+    AddAdmin {
+        address: String,
+    },
+    SetPriceSource {
+        source: PriceSource,
+    },
+    AddFeeder {
+        address: String,
+    },
+    RemoveFeeder {
+        address: String,
+    },
+    SetLock {
+        market_id: String,
+        lock: bool,
+    },
+    SetPriceGuard {
+        guard: PriceGuard,
+    },
+    SetExposureLimits {
+        denom: Denom,
+        limits: ExposureLimits,
+    },
+    SetOddsMode {
+        mode: OddsMode,
+    },
+    //None removes the limit
+    SetCreditLine {
+        denom: Denom,
+        amount: Option<Uint128>,
+    },
+}
+
+// payload of ExecuteMsg::Receive
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Betting {
+        market_id: String,
+        position: String,
+        duration: u64,
+    },
+    Deposit {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetState {},
-    GetBalance { address: String },
-    GetRoundPrice { height: u64 },
-    GetLatestPrice {},
-    GetHeightBettingList { target_height: u64 },
-    GetisLock {},
-    GetRecentBettingList { target_height: u64 },
+    GetMarket {
+        market_id: String,
+    },
+    GetMarkets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetDurations {
+        market_id: String,
+    },
+    GetDenoms {},
+    GetCw20Tokens {},
+    GetBet {
+        id: u64,
+    },
+    //bettings of the address, lowest id first, only the given status when Some
+    BetsByAddress {
+        address: String,
+        status: Option<BetStatus>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetBalance {
+        address: String,
+        denom: Denom,
+    },
+    //settlement payouts not claimable yet
+    GetHeldBalance {
+        address: String,
+        denom: Denom,
+    },
+    GetRoundPrice {
+        market_id: String,
+        height: u64,
+    },
+    GetLatestPrice {
+        market_id: String,
+    },
+    GetHeightBettingList {
+        market_id: String,
+        target_height: u64,
+    },
+    GetisLock {
+        market_id: String,
+    },
+    GetRecentBettingList {
+        market_id: String,
+        target_height: u64,
+    },
+    GetSettlement {
+        market_id: String,
+        target_height: u64,
+    },
+    GetOpenHeights {
+        market_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetOpenStake {
+        address: String,
+        denom: Denom,
+    },
+    GetExposure {
+        market_id: String,
+        target_height: u64,
+        denom: Denom,
+    },
+    GetExposureLimits {
+        denom: Denom,
+    },
+    GetBankLedger {
+        denom: Denom,
+    },
+    GetHousePool {
+        denom: Denom,
+    },
+    GetLpShares {
+        address: String,
+        denom: Denom,
+    },
+    //held and owed amounts of the denom against the contract balance and the bank's record
+    Solvency {
+        denom: Denom,
+    },
+    //bank contract version and figures as seen by its queries
+    GetBankInfo {
+        denom: Denom,
+    },
+    GetPendingPayBacks {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetPendingDraws {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    //dry run of ExecuteMsg::Betting, address enables the open stake check
+    QuoteBet {
+        market_id: String,
+        position: String,
+        duration: u64,
+        amount: Uint128,
+        denom: Denom,
+        address: Option<String>,
+    },
+    //payout multiplier per position for a betting of amount placed now
+    GetOdds {
+        market_id: String,
+        duration: u64,
+        amount: Uint128,
+        denom: Denom,
+    },
+    //what Setting with this price would pay for the target height, nothing is saved
+    PreviewSettlement {
+        market_id: String,
+        height: u64,
+        price: Decimal,
+    },
+    GetRoundResult {
+        market_id: String,
+        height: u64,
+    },
+    ListRoundResults {
+        market_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetFeeders {},
+    //current round when None
+    GetFeedRound {
+        market_id: String,
+        round: Option<u64>,
+    },
 }
 
 #[cw_serde]
@@ -46,9 +321,169 @@ pub struct AllowanceAndTotalSupplyResponse {
 // We define a custom struct for each query response
 
 #[cw_serde]
-pub enum AMGBankMsg {
-    Deposit {},
-    Withdraw {},
-    BorrowBalance { amount: Uint128 },
-    PayBack {},
+pub struct SettlementResponse {
+    pub market_id: String,
+    pub target_height: u64,
+    pub queued: bool,
+    //bettings of the height already settled, when settlement is in progress
+    pub settled_index: Option<u64>,
+    //block height the settlement finished at
+    pub settled_at: Option<u64>,
+}
+
+#[cw_serde]
+pub struct AccountPayout {
+    pub address: Addr,
+    pub denom: Denom,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct PreviewSettlementResponse {
+    pub market_id: String,
+    pub height: u64,
+    pub winners: u64,
+    pub losers: u64,
+    //credited to winners
+    pub payouts: Vec<DenomAmount>,
+    //stakes of losers, owed to the bank
+    pub pay_back: Vec<DenomAmount>,
+    //pool part of the payouts, owed by the bank
+    pub draw: Vec<DenomAmount>,
+    pub accounts: Vec<AccountPayout>,
+}
+
+#[cw_serde]
+pub struct RoundResultResponse {
+    pub market_id: String,
+    pub height: u64,
+    pub result: RoundResult,
+}
+
+#[cw_serde]
+pub struct PendingPayBackResponse {
+    pub id: u64,
+    pub pay_back: PendingPayBack,
+}
+
+#[cw_serde]
+pub struct PendingDrawResponse {
+    pub id: u64,
+    pub draw: PendingDraw,
+}
+
+#[cw_serde]
+pub struct MarketResponse {
+    pub market_id: String,
+    pub market: Market,
+}
+
+#[cw_serde]
+pub struct DenomResponse {
+    pub denom: String,
+    pub minimum_amount: Uint128,
+    pub maximum_amount: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct DurationResponse {
+    pub duration: u64,
+    pub config: DurationConfig,
+}
+
+#[cw_serde]
+pub struct QuoteResponse {
+    pub target_height: u64,
+    pub base_price: Option<Decimal>,
+    pub win_amount: Option<Uint128>,
+    //the error Betting would fail with
+    pub error: Option<String>,
+}
+
+#[cw_serde]
+pub struct OddsResponse {
+    pub target_height: u64,
+    //win_amount / betting_amount
+    pub long: Decimal,
+    pub short: Decimal,
+    pub equal: Decimal,
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    pub price: Decimal,
+    pub precision: u32,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct FeederSubmissionResponse {
+    pub feeder: Addr,
+    pub price: Decimal,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct FeedRoundResponse {
+    pub market_id: String,
+    pub round: u64,
+    //median, once the round reached quorum
+    pub price: Option<PriceResponse>,
+    pub submissions: Vec<FeederSubmissionResponse>,
+}
+
+#[cw_serde]
+pub struct HousePoolResponse {
+    pub total_shares: Uint128,
+    pub value: Uint128,
+    //pool part of open bettings
+    pub reserved: Uint128,
+    //winnings of settled heights the pool could not cover, paid by RetryDraw
+    pub debt: Uint128,
+    //value - reserved - debt, what withdrawals and new bettings can use
+    pub free: Uint128,
+}
+
+#[cw_serde]
+pub struct LpSharesResponse {
+    pub shares: Uint128,
+    //part of the pool value, debt paid
+    pub value: Uint128,
+    //part of the free liquidity, what Withdraw pays for the shares now
+    pub withdrawable: Uint128,
+}
+
+#[cw_serde]
+pub struct SolvencyResponse {
+    pub denom: Denom,
+    //BorrowBalance received from the bank
+    pub total_borrowed: Uint128,
+    //PayBack accepted by the bank
+    pub total_repaid: Uint128,
+    //total_borrowed - total_repaid, negative when the bank got back more than it lent
+    pub total_outstanding: SignedAmount,
+    //pool part of open bettings, owed by the bank if they all win
+    pub reserved: Uint128,
+    pub pending_pay_back: Uint128,
+    pub pending_draw: Uint128,
+    //open stakes + claimable balances + pending PayBack - pending draws,
+    //+ house pool value in house pool mode
+    pub expected_balance: Uint128,
+    pub actual_balance: Uint128,
+    //counters of the bank, None when it does not answer or in house pool mode
+    pub bank_borrowed: Option<Uint128>,
+    pub bank_repaid: Option<Uint128>,
+    //actual_balance covers expected_balance
+    pub solvent: bool,
+    pub discrepancies: Vec<String>,
+}
+
+#[cw_serde]
+pub struct BankInfoResponse {
+    pub contract: Addr,
+    pub version: ContractVersion,
+    pub liquidity: LiquidityResponse,
+    //drawn and paid back by this contract in total
+    pub borrowed: BorrowedResponse,
+    pub lp_token: LpTokenInfoResponse,
 }
@@ -0,0 +1,30 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, QuerierWrapper, StdResult};
+
+/// Query interface the game expects from a price oracle contract.
+#[cw_serde]
+pub enum OracleQueryMsg {
+    /// Returns the latest price of the market.
+    /// Return type: OraclePriceResponse.
+    Price { market_id: String },
+}
+
+#[cw_serde]
+pub struct OraclePriceResponse {
+    pub price: Decimal,
+    //block height the oracle updated the price at
+    pub height: u64,
+}
+
+pub fn query_oracle_price(
+    querier: &QuerierWrapper,
+    oracle: &Addr,
+    market_id: &str,
+) -> StdResult<OraclePriceResponse> {
+    querier.query_wasm_smart(
+        oracle,
+        &OracleQueryMsg::Price {
+            market_id: market_id.to_string(),
+        },
+    )
+}
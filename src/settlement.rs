@@ -0,0 +1,404 @@
+use cosmwasm_std::{Env, Order, StdResult, Storage, Uint128};
+use cw20::Denom;
+use cw_storage_plus::Bound;
+use std::cmp::Ordering::*;
+
+use crate::helper::add_denom_amount;
+use crate::state::{
+    bettings, close_betting, denom_key, height_bettings, hold_balance, load_state,
+    next_transfer_id, BetStatus, Betting, DenomAmount, MissedRoundPolicy, PendingDraw,
+    PendingPayBack, PoolMode, Position, PriceRange, RoundResult, SettleCursor, SettleTask,
+    BANK_LEDGER, DRAWS, HEIGHT_DRAWS, HOUSE_POOLS, OPEN_HEIGHTS, PAY_BACKS, PRICES, ROUND_RESULTS,
+    SETTLED_HEIGHTS, SETTLE_CURSOR, SETTLE_QUEUE,
+};
+
+//net amount of one settled height and denom
+pub enum BankTransfer {
+    //pay back id, saved in PAY_BACKS until the bank accepts it
+    PayBack(u64, DenomAmount),
+    //draw id, saved in DRAWS until the bank sends it
+    Draw(u64, DenomAmount),
+}
+
+pub struct SettleOutcome {
+    //number of bettings processed in this call
+    pub settled: u64,
+    //one per denom of every height finished in this call
+    pub transfers: Vec<BankTransfer>,
+    pub attrs: Vec<(String, String)>,
+}
+
+//what one betting pays to the bettor, owes to the bank and draws from it
+pub struct BettingSettlement {
+    pub status: BetStatus,
+    pub payout: Uint128,
+    pub pay_back: Uint128,
+    pub draw: Uint128,
+}
+
+//win/lose classification of one betting, no state involved
+pub fn settle_betting(betting: &Betting, task: &SettleTask) -> BettingSettlement {
+    match task {
+        SettleTask::Round { price } => {
+            let win_position = match betting.base_price.cmp(price) {
+                Less => Position::Long,
+                Equal => Position::Eqaul,
+                Greater => Position::Short,
+            };
+
+            //the stake is held here, only the pool part moves with the bank
+            match win_position == betting.position {
+                true => BettingSettlement {
+                    status: BetStatus::Won,
+                    payout: betting.win_amount,
+                    pay_back: Uint128::zero(),
+                    draw: betting.win_amount - betting.amount,
+                },
+                false => BettingSettlement {
+                    status: BetStatus::Lost,
+                    payout: Uint128::zero(),
+                    pay_back: betting.amount,
+                    draw: Uint128::zero(),
+                },
+            }
+        }
+        //betting amount 는 수수료 포함 금액
+        SettleTask::Refund {} => BettingSettlement {
+            status: BetStatus::Refunded,
+            payout: betting.amount,
+            pay_back: Uint128::zero(),
+            draw: Uint128::zero(),
+        },
+    }
+}
+
+pub fn enqueue_settlement(
+    storage: &mut dyn Storage,
+    market_id: &str,
+    target_height: u64,
+    task: &SettleTask,
+) -> StdResult<()> {
+    let key = (market_id, target_height);
+    //a height is settled only once
+    if SETTLED_HEIGHTS.has(storage, key) || SETTLE_QUEUE.has(storage, key) {
+        return Ok(());
+    }
+    SETTLE_QUEUE.save(storage, key, task)
+}
+
+/*
+Move open target heights of the market up to `up_to` into the settle queue, lowest first.
+A height is settled against its own round price when there is one,
+otherwise the missed round policy decides between the nearest later price and a refund.
+ */
+pub fn resolve_open_heights(
+    storage: &mut dyn Storage,
+    market_id: &str,
+    up_to: u64,
+    policy: &MissedRoundPolicy,
+    limit: u64,
+) -> StdResult<u64> {
+    let heights = OPEN_HEIGHTS
+        .prefix(market_id)
+        .keys(
+            storage,
+            None,
+            Some(Bound::inclusive(up_to)),
+            Order::Ascending,
+        )
+        .take(limit as usize)
+        .collect::<StdResult<Vec<u64>>>()?;
+
+    for height in &heights {
+        let task = match PRICES.may_load(storage, (market_id, *height))? {
+            Some(price) => SettleTask::Round { price },
+            None => match policy {
+                MissedRoundPolicy::NextPrice {} => {
+                    let next_price = PRICES
+                        .prefix(market_id)
+                        .range(
+                            storage,
+                            Some(Bound::exclusive(*height)),
+                            None,
+                            Order::Ascending,
+                        )
+                        .next()
+                        .transpose()?;
+                    match next_price {
+                        Some((_, price)) => SettleTask::Round { price },
+                        None => SettleTask::Refund {},
+                    }
+                }
+                MissedRoundPolicy::Refund {} => SettleTask::Refund {},
+            },
+        };
+        enqueue_settlement(storage, market_id, *height, &task)?;
+        OPEN_HEIGHTS.remove(storage, (market_id, *height));
+    }
+
+    Ok(heights.len() as u64)
+}
+
+/*
+Settle at most `limit` bettings from the queue.
+The in-progress height is resumed first from the saved cursor,
+then the queue is walked from the lowest target height.
+A height spread over several calls is squared with the bank once, in the call that finishes it.
+ */
+pub fn settle_pending(
+    storage: &mut dyn Storage,
+    env: &Env,
+    limit: u64,
+) -> StdResult<SettleOutcome> {
+    let mut outcome = SettleOutcome {
+        settled: 0,
+        transfers: vec![],
+        attrs: vec![],
+    };
+
+    while outcome.settled < limit {
+        let cursor = match SETTLE_CURSOR.may_load(storage)? {
+            Some(cursor) => cursor,
+            None => {
+                let next = SETTLE_QUEUE
+                    .keys(storage, None, None, Order::Ascending)
+                    .next()
+                    .transpose()?;
+                match next {
+                    Some((market_id, height)) => SettleCursor {
+                        market_id,
+                        height,
+                        index: 0,
+                        last_id: 0,
+                    },
+                    None => break,
+                }
+            }
+        };
+
+        let key = (cursor.market_id.as_str(), cursor.height);
+        let task = SETTLE_QUEUE.load(storage, key)?;
+        let batch = height_bettings(
+            storage,
+            &cursor.market_id,
+            cursor.height,
+            Some(&BetStatus::Open),
+            Some(cursor.last_id),
+            Some((limit - outcome.settled) as usize),
+        )?;
+        let last_id = batch.last().map_or(cursor.last_id, |betting| betting.id);
+
+        let settle_price = match &task {
+            SettleTask::Round { price } => Some(*price),
+            SettleTask::Refund {} => None,
+        };
+        let mut result = match ROUND_RESULTS.may_load(storage, key)? {
+            Some(result) => result,
+            None => RoundResult {
+                base_price: None,
+                settle_price,
+                bettings: 0,
+                ties: 0,
+                total_staked: vec![],
+                total_paid: vec![],
+                total_returned: vec![],
+                total_drawn: vec![],
+                settled_at: env.block.height,
+            },
+        };
+
+        for betting in &batch {
+            close_betting(storage, betting)?;
+            let settlement = settle_betting(betting, &task);
+
+            //settled bettings are kept for the bet history
+            let mut settled = betting.clone();
+            settled.status = settlement.status.clone();
+            settled.settle_price = settle_price;
+            bettings().replace(storage, betting.id, Some(&settled), Some(betting))?;
+
+            result.bettings += 1;
+            result.base_price = Some(PriceRange::widen(
+                result.base_price.take(),
+                betting.base_price,
+            ));
+            if settle_price == Some(betting.base_price) {
+                result.ties += 1;
+            }
+            add_denom_amount(&mut result.total_staked, &betting.denom, betting.amount);
+            add_denom_amount(&mut result.total_paid, &betting.denom, settlement.payout);
+            add_denom_amount(
+                &mut result.total_returned,
+                &betting.denom,
+                settlement.pay_back,
+            );
+            add_denom_amount(&mut result.total_drawn, &betting.denom, settlement.draw);
+            if settlement.payout.is_zero() {
+                continue;
+            }
+            hold_balance(
+                storage,
+                &betting.address,
+                &betting.denom,
+                &cursor.market_id,
+                cursor.height,
+                settlement.payout,
+            )?;
+            if settlement.status == BetStatus::Won {
+                outcome.attrs.push((
+                    betting.address.to_string(),
+                    format!("{}{}", settlement.payout, denom_key(&betting.denom)),
+                ));
+            }
+        }
+        outcome.settled += batch.len() as u64;
+        result.settled_at = env.block.height;
+        ROUND_RESULTS.save(storage, key, &result)?;
+
+        let done = height_bettings(
+            storage,
+            &cursor.market_id,
+            cursor.height,
+            Some(&BetStatus::Open),
+            Some(last_id),
+            Some(1),
+        )?
+        .is_empty();
+        match done {
+            true => {
+                outcome.transfers.extend(net_transfers(
+                    storage,
+                    &cursor.market_id,
+                    cursor.height,
+                    &result,
+                )?);
+                SETTLE_QUEUE.remove(storage, key);
+                SETTLE_CURSOR.remove(storage);
+                SETTLED_HEIGHTS.save(storage, key, &env.block.height)?;
+                outcome.attrs.push((
+                    "settled_height".to_string(),
+                    format!("{}/{}", cursor.market_id, cursor.height),
+                ));
+            }
+            false => SETTLE_CURSOR.save(
+                storage,
+                &SettleCursor {
+                    market_id: cursor.market_id.clone(),
+                    height: cursor.height,
+                    index: cursor.index + batch.len() as u64,
+                    last_id,
+                },
+            )?,
+        }
+    }
+
+    Ok(outcome)
+}
+
+//winnings of the height stay held while the draw is in DRAWS
+fn queue_draw(
+    storage: &mut dyn Storage,
+    market_id: &str,
+    height: u64,
+    denom: &Denom,
+    amount: Uint128,
+) -> StdResult<u64> {
+    let id = next_transfer_id(storage)?;
+    DRAWS.save(
+        storage,
+        id,
+        &PendingDraw {
+            market_id: market_id.to_string(),
+            height,
+            denom: denom.clone(),
+            amount,
+            attempts: 0,
+            error: None,
+        },
+    )?;
+    HEIGHT_DRAWS.save(storage, (market_id, height, &denom_key(denom)), &id)?;
+    Ok(id)
+}
+
+//net the returned and drawn amounts of a finished height and record them in the bank ledger,
+//or in the house pool when there is no bank
+fn net_transfers(
+    storage: &mut dyn Storage,
+    market_id: &str,
+    height: u64,
+    result: &RoundResult,
+) -> StdResult<Vec<BankTransfer>> {
+    let pool_mode = load_state(storage)?.pool_mode;
+    let mut transfers = vec![];
+    for returned in result.total_returned.iter() {
+        let drawn = result
+            .total_drawn
+            .iter()
+            .find(|drawn| drawn.denom == returned.denom)
+            .map_or(Uint128::zero(), |drawn| drawn.amount);
+
+        let denom = denom_key(&returned.denom);
+        let mut ledger = BANK_LEDGER.may_load(storage, &denom)?.unwrap_or_default();
+        //house pool, the outcome only changes the pool value,
+        //winnings it cannot cover wait as a pending draw for RetryDraw
+        if pool_mode == (PoolMode::House {}) {
+            let mut pool = HOUSE_POOLS.may_load(storage, &denom)?.unwrap_or_default();
+            let available = pool.value + returned.amount;
+            match available.cmp(&drawn) {
+                Less => {
+                    let shortfall = drawn - available;
+                    pool.value = Uint128::zero();
+                    ledger.pending_draw += shortfall;
+                    queue_draw(storage, market_id, height, &returned.denom, shortfall)?;
+                    BANK_LEDGER.save(storage, &denom, &ledger)?;
+                }
+                Greater | Equal => pool.value = available - drawn,
+            }
+            HOUSE_POOLS.save(storage, &denom, &pool)?;
+            continue;
+        }
+
+        let transfer = match returned.amount.cmp(&drawn) {
+            Greater => {
+                let amount = returned.amount - drawn;
+                ledger.pending_pay_back += amount;
+                let id = next_transfer_id(storage)?;
+                PAY_BACKS.save(
+                    storage,
+                    id,
+                    &PendingPayBack {
+                        market_id: market_id.to_string(),
+                        height,
+                        denom: returned.denom.clone(),
+                        amount,
+                        attempts: 0,
+                        error: None,
+                    },
+                )?;
+                BankTransfer::PayBack(
+                    id,
+                    DenomAmount {
+                        denom: returned.denom.clone(),
+                        amount,
+                    },
+                )
+            }
+            Less => {
+                let amount = drawn - returned.amount;
+                ledger.pending_draw += amount;
+                let id = queue_draw(storage, market_id, height, &returned.denom, amount)?;
+                BankTransfer::Draw(
+                    id,
+                    DenomAmount {
+                        denom: returned.denom.clone(),
+                        amount,
+                    },
+                )
+            }
+            Equal => continue,
+        };
+        BANK_LEDGER.save(storage, &denom, &ledger)?;
+        transfers.push(transfer);
+    }
+    Ok(transfers)
+}
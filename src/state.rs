@@ -1,20 +1,28 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, StdResult, Storage, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Order, StdResult, Storage, Uint128, Uint256};
+use cw20::Denom;
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use std::convert::TryFrom;
 
 use crate::ContractError;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub admin: Vec<Addr>,
-    pub fee_late: u8,
-    pub denom: String,
-    pub minimum_amount: Uint128,
     pub bank_contract: Addr,
-    pub latest_price: Uint128,
-    pub lock: bool,
+    //max bettings settled by one Setting, the rest is left for Settle
+    pub settle_limit: u64,
+    pub missed_round_policy: MissedRoundPolicy,
+    //blocks after target_height before a betting without round price can be refunded
+    pub refund_timeout: u64,
+    //max share of the bank's free liquidity one betting may borrow, 10000 = 100%
+    pub max_pool_share_bps: u64,
+    pub price_source: PriceSource,
+    pub price_guard: PriceGuard,
+    pub odds_mode: OddsMode,
+    pub pool_mode: PoolMode,
 }
 
 pub fn save_state(storage: &mut dyn Storage, state: &State) -> StdResult<()> {
@@ -25,6 +33,170 @@ pub fn load_state(storage: &dyn Storage) -> StdResult<State> {
     STATE.load(storage)
 }
 
+//one price series (asset pair) with its own betting config
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Market {
+    pub lock: bool,
+    //decimal places of every stored price
+    pub price_precision: u32,
+    pub latest_price: Uint128,
+    //block height the latest price was set at
+    pub latest_price_height: u64,
+}
+
+//betting config of one allowed duration
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DurationConfig {
+    pub fee_late: u8,
+}
+
+//accepted betting coin, stake limits are in the raw units of the denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomConfig {
+    pub minimum_amount: Uint128,
+    pub maximum_amount: Option<Uint128>,
+}
+
+pub fn save_market(storage: &mut dyn Storage, market_id: &str, market: &Market) -> StdResult<()> {
+    MARKETS.save(storage, market_id, market)
+}
+
+pub fn load_market(storage: &dyn Storage, market_id: &str) -> StdResult<Market> {
+    MARKETS.load(storage, market_id)
+}
+
+//BALANCE key of a native denom or cw20 token
+pub fn denom_key(denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => denom.clone(),
+        Denom::Cw20(address) => format!("cw20:{}", address),
+    }
+}
+
+//add a new betting to OPEN_STAKE, EXPOSURE and the reserved amount of the bank ledger
+pub fn open_betting(
+    storage: &mut dyn Storage,
+    betting: &Betting,
+    open_stake: Uint128,
+    exposure: &Exposure,
+) -> StdResult<()> {
+    let denom = denom_key(&betting.denom);
+    OPEN_STAKE.save(storage, (&betting.address, &denom), &open_stake)?;
+    EXPOSURE.save(
+        storage,
+        (&betting.market_id, betting.target_height, &denom),
+        exposure,
+    )?;
+    BANK_LEDGER.update(storage, &denom, |ledger| -> StdResult<_> {
+        let mut ledger = ledger.unwrap_or_default();
+        ledger.reserved += betting.win_amount - betting.amount;
+        ledger.open_stake += betting.amount;
+        Ok(ledger)
+    })?;
+    Ok(())
+}
+
+//take a settled or refunded betting out of OPEN_STAKE, EXPOSURE and the reserved amount
+pub fn close_betting(storage: &mut dyn Storage, betting: &Betting) -> StdResult<()> {
+    let denom = denom_key(&betting.denom);
+
+    let stake_key = (&betting.address, denom.as_str());
+    let open_stake = OPEN_STAKE
+        .may_load(storage, stake_key)?
+        .unwrap_or_default()
+        .saturating_sub(betting.amount);
+    match open_stake.is_zero() {
+        true => OPEN_STAKE.remove(storage, stake_key),
+        false => OPEN_STAKE.save(storage, stake_key, &open_stake)?,
+    }
+
+    let exposure_key = (
+        betting.market_id.as_str(),
+        betting.target_height,
+        denom.as_str(),
+    );
+    let mut exposure = EXPOSURE
+        .may_load(storage, exposure_key)?
+        .unwrap_or_default();
+    exposure.sub(&betting.position, betting.amount);
+    match exposure.is_empty() {
+        true => EXPOSURE.remove(storage, exposure_key),
+        false => EXPOSURE.save(storage, exposure_key, &exposure)?,
+    }
+
+    let mut ledger = BANK_LEDGER.may_load(storage, &denom)?.unwrap_or_default();
+    ledger.reserved = ledger
+        .reserved
+        .saturating_sub(betting.win_amount - betting.amount);
+    ledger.open_stake = ledger.open_stake.saturating_sub(betting.amount);
+    BANK_LEDGER.save(storage, &denom, &ledger)
+}
+
+//settlement payout, held until its height is settled and the bank has sent its draw
+pub fn hold_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    denom: &Denom,
+    market_id: &str,
+    height: u64,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let denom = denom_key(denom);
+    BANK_LEDGER.update(storage, &denom, |ledger| -> StdResult<_> {
+        let mut ledger = ledger.unwrap_or_default();
+        ledger.claimable += amount;
+        Ok(ledger)
+    })?;
+    HELD_BALANCE.update(
+        storage,
+        (address, &denom, (market_id, height)),
+        |exsists| -> StdResult<_> {
+            match exsists {
+                Some(balance) => Ok(balance + amount),
+                None => Ok(amount),
+            }
+        },
+    )
+}
+
+//held payouts of the address whose height is settled and owes no draw
+pub fn releasable_balance(
+    storage: &dyn Storage,
+    address: &Addr,
+    denom: &str,
+) -> StdResult<Vec<((String, u64), Uint128)>> {
+    HELD_BALANCE
+        .prefix((address, denom))
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok(((market_id, height), _)) => {
+                SETTLED_HEIGHTS.has(storage, (market_id, *height))
+                    && !HEIGHT_DRAWS.has(storage, (market_id, *height, denom))
+            }
+            Err(_) => true,
+        })
+        .collect()
+}
+
+//move the releasable held payouts of the address to BALANCE
+pub fn release_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    denom: &str,
+) -> StdResult<Uint128> {
+    let mut released = Uint128::zero();
+    for ((market_id, height), amount) in releasable_balance(storage, address, denom)? {
+        HELD_BALANCE.remove(storage, (address, denom, (&market_id, height)));
+        released += amount;
+    }
+    if !released.is_zero() {
+        BALANCE.update(storage, (address, denom), |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default() + released)
+        })?;
+    }
+    Ok(released)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum Position {
     Long,
@@ -43,19 +215,38 @@ impl Position {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BetStatus {
+    Open,
+    Won,
+    Lost,
+    Refunded,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Betting {
+    pub id: u64,
+    pub market_id: String,
     pub address: Addr,
+    pub denom: Denom,
     pub start_height: u64,
     pub target_height: u64,
     pub position: Position,
     pub base_price: Uint128,
     pub amount: Uint128,
     pub win_amount: Uint128,
+    pub status: BetStatus,
+    //round price the betting was settled against, None while open or when refunded
+    pub settle_price: Option<Uint128>,
 }
 impl Betting {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
+        id: u64,
+        market_id: String,
         address: Addr,
+        denom: Denom,
         position: Position,
         amount: Uint128,
         win_amount: Uint128,
@@ -64,20 +255,494 @@ impl Betting {
         target_height: u64,
     ) -> Self {
         Betting {
+            id,
+            market_id,
             address,
+            denom,
             position,
             amount,
             base_price,
             start_height,
             target_height,
             win_amount,
+            status: BetStatus::Open,
+            settle_price: None,
+        }
+    }
+}
+
+//who pushes round prices
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    //admin Setting
+    Admin {},
+    //anyone can UpdatePrice from the oracle contract, with a price at most max_age blocks old
+    Oracle { address: Addr, max_age: Option<u64> },
+    //median of the registered feeders, once quorum feeders submitted within max_age blocks
+    Feeders { quorum: u32, max_age: Option<u64> },
+}
+
+//where the pool part of a win amount comes from
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolMode {
+    //lent by the bank contract
+    Bank {},
+    //LPs deposit into this contract and own the pool through LP_SHARES
+    House {},
+}
+
+/*
+Virtual shares and value added to every share price.
+A drained pool still takes deposits, and value left in a pool without shares
+is spread over later depositors instead of going to the next one alone.
+ */
+const VIRTUAL_SHARES: u128 = 1000;
+const VIRTUAL_VALUE: u128 = 1;
+
+//house pool of one denom
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct HousePool {
+    pub total_shares: Uint128,
+    //deposits plus the net outcome of settled heights
+    pub value: Uint128,
+}
+
+//debt is the pending draw of the denom, the winnings the pool could not cover yet
+impl HousePool {
+    //value the shares own once the debt is paid
+    pub fn net_value(&self, debt: Uint128) -> Uint128 {
+        self.value.saturating_sub(debt)
+    }
+
+    pub fn shares_for(&self, amount: Uint128, debt: Uint128) -> Uint128 {
+        amount.multiply_ratio(
+            self.total_shares + Uint128::new(VIRTUAL_SHARES),
+            self.net_value(debt) + Uint128::new(VIRTUAL_VALUE),
+        )
+    }
+
+    pub fn value_of(&self, shares: Uint128, debt: Uint128) -> Uint128 {
+        shares.multiply_ratio(
+            self.net_value(debt) + Uint128::new(VIRTUAL_VALUE),
+            self.total_shares + Uint128::new(VIRTUAL_SHARES),
+        )
+    }
+
+    //shares worth amount, rounded up
+    pub fn shares_of(&self, amount: Uint128, debt: Uint128) -> StdResult<Uint128> {
+        let value = Uint256::from(self.net_value(debt) + Uint128::new(VIRTUAL_VALUE));
+        let shares = amount.full_mul(self.total_shares + Uint128::new(VIRTUAL_SHARES));
+        Ok(Uint128::try_from(
+            (shares + value - Uint256::one()) / value,
+        )?)
+    }
+
+    //part of free liquidity the shares can withdraw now
+    pub fn free_value_of(&self, shares: Uint128, free: Uint128) -> Uint128 {
+        shares.multiply_ratio(free, self.total_shares + Uint128::new(VIRTUAL_SHARES))
+    }
+}
+
+//checks on a new price against latest_price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct PriceGuard {
+    //10000 = 100%
+    pub max_deviation_bps: Option<u64>,
+    //max blocks since latest_price_height for a betting on latest_price,
+    //the first price after a longer gap skips the deviation check
+    pub max_block_gap: Option<u64>,
+    //lock the contract instead of rejecting an out of band price
+    pub lock_on_violation: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomAmount {
+    pub denom: Denom,
+    pub amount: Uint128,
+}
+
+//amount below zero when negative
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SignedAmount {
+    pub amount: Uint128,
+    pub negative: bool,
+}
+
+//lowest and highest of a set of prices
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceRange {
+    pub low: Uint128,
+    pub high: Uint128,
+}
+
+impl PriceRange {
+    pub fn widen(range: Option<PriceRange>, price: Uint128) -> PriceRange {
+        match range {
+            Some(range) => PriceRange {
+                low: range.low.min(price),
+                high: range.high.max(price),
+            },
+            None => PriceRange {
+                low: price,
+                high: price,
+            },
         }
     }
 }
 
+/*
+Outcome of one settled target height, filled in batch by batch.
+Bettings of different durations on one height start from different prices,
+so the base price is kept as the range of the settled bettings' base prices.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundResult {
+    //low == high when every betting started from one price, None before the first betting
+    pub base_price: Option<PriceRange>,
+    //None when the height was refunded
+    pub settle_price: Option<Uint128>,
+    pub bettings: u64,
+    //bettings whose base price equals the settle price, won by equal
+    pub ties: u64,
+    pub total_staked: Vec<DenomAmount>,
+    //credited to bettors, winnings and refunds
+    pub total_paid: Vec<DenomAmount>,
+    //stakes of lost bettings, owed to the bank
+    pub total_returned: Vec<DenomAmount>,
+    //pool part of won bettings, owed by the bank
+    pub total_drawn: Vec<DenomAmount>,
+    //block height the last betting was settled at
+    pub settled_at: u64,
+}
+
+/*
+Credit line of the bank contract for one denom and what this contract holds of it.
+A betting reserves the pool part of its win amount here instead of borrowing it,
+and each settled height is squared with the bank by one net PayBack or BorrowBalance.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct BankLedger {
+    //maximum reserved amount, None = limited by the bank pool only
+    pub credit_line: Option<Uint128>,
+    //pool part of the win amount of open bettings
+    pub reserved: Uint128,
+    //PayBack accepted by the bank, its repaid counter
+    pub paid_back: Uint128,
+    //BorrowBalance received from the bank, its borrowed counter
+    pub drawn: Uint128,
+    //PayBack not accepted by the bank yet, held here until it is
+    pub pending_pay_back: Uint128,
+    //BorrowBalance not received yet, the winnings of its height are held until it is
+    pub pending_draw: Uint128,
+    //stakes of open bettings, held here
+    pub open_stake: Uint128,
+    //credited to BALANCE or HELD_BALANCE and not claimed yet, held here
+    pub claimable: Uint128,
+}
+
+impl BankLedger {
+    //drawn - paid_back, negative when the bank got back more than it lent
+    pub fn net_borrowed(&self) -> SignedAmount {
+        match self.drawn >= self.paid_back {
+            true => SignedAmount {
+                amount: self.drawn - self.paid_back,
+                negative: false,
+            },
+            false => SignedAmount {
+                amount: self.paid_back - self.drawn,
+                negative: true,
+            },
+        }
+    }
+
+    //what the contract balance of the denom must cover,
+    //claimable includes the held winnings of pending draws the bank has not sent yet
+    pub fn held(&self) -> Uint128 {
+        self.open_stake + self.claimable + self.pending_pay_back - self.pending_draw
+    }
+}
+
+//net PayBack of one settled height, removed once the bank accepted it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPayBack {
+    pub market_id: String,
+    pub height: u64,
+    pub denom: Denom,
+    pub amount: Uint128,
+    //failed attempts
+    pub attempts: u64,
+    //error of the last attempt
+    pub error: Option<String>,
+}
+
+//stake limits in the raw units of one denom, None = no limit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ExposureLimits {
+    //stake of one betting
+    pub max_bet_amount: Option<Uint128>,
+    //stake of one address over all open bettings
+    pub max_open_stake: Option<Uint128>,
+    //|long - short| stake of one target height
+    pub max_net_exposure: Option<Uint128>,
+}
+
+//how the payout of a new betting is priced
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OddsMode {
+    //payout rate = 1 - fee_late
+    Fixed {},
+    /*
+    payout rate moves with the long/short imbalance of the target height,
+    skew = (same side stake - other side stake) / both sides stake
+    payout rate = (1 - fee_late) - skew * sensitivity_bps, kept within the bounds
+    10000 = 100%
+     */
+    Dynamic {
+        sensitivity_bps: u64,
+        min_payout_bps: u64,
+        max_payout_bps: u64,
+    },
+}
+
+//stake per side of one target height
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Exposure {
+    pub long: Uint128,
+    pub short: Uint128,
+    pub equal: Uint128,
+}
+
+impl Exposure {
+    pub fn add(&mut self, position: &Position, amount: Uint128) {
+        match position {
+            Position::Long => self.long += amount,
+            Position::Short => self.short += amount,
+            Position::Eqaul => self.equal += amount,
+        }
+    }
+
+    pub fn sub(&mut self, position: &Position, amount: Uint128) {
+        match position {
+            Position::Long => self.long = self.long.saturating_sub(amount),
+            Position::Short => self.short = self.short.saturating_sub(amount),
+            Position::Eqaul => self.equal = self.equal.saturating_sub(amount),
+        }
+    }
+
+    pub fn net(&self) -> Uint128 {
+        match self.long > self.short {
+            true => self.long - self.short,
+            false => self.short - self.long,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.long.is_zero() && self.short.is_zero() && self.equal.is_zero()
+    }
+}
+
+//how to settle a target height that never got its own round price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRoundPolicy {
+    NextPrice {},
+    Refund {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SettleTask {
+    Round { price: Uint128 },
+    Refund {},
+}
+
+//height in settlement, index = bettings of the height already settled
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SettleCursor {
+    pub market_id: String,
+    pub height: u64,
+    pub index: u64,
+    //id of the last settled betting
+    pub last_id: u64,
+}
+
+//net BorrowBalance of one settled height, removed once the bank sent it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingDraw {
+    pub market_id: String,
+    pub height: u64,
+    pub denom: Denom,
+    pub amount: Uint128,
+    //failed attempts
+    pub attempts: u64,
+    //error of the last attempt
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeederSubmission {
+    pub price: Uint128,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeedRound {
+    //median of the submissions
+    pub price: Uint128,
+    //block height the round was finalized at
+    pub height: u64,
+    //fresh submissions the median was taken over
+    pub submissions: u32,
+}
+
 pub const STATE: Item<State> = Item::new("state");
-// key - target_height
-pub const BETTINGS: Map<u64, Vec<Betting>> = Map::new("bettings");
+// key - denom
+pub const DENOMS: Map<&str, DenomConfig> = Map::new("denoms");
+// key - whitelisted cw20 contract
+pub const CW20_TOKENS: Map<&Addr, DenomConfig> = Map::new("cw20_tokens");
+// key - market_id
+pub const MARKETS: Map<&str, Market> = Map::new("markets");
+// key - (market_id, duration in blocks)
+pub const DURATIONS: Map<(&str, u64), DurationConfig> = Map::new("durations");
+// last betting id handed out
+pub const BETTING_ID: Item<u64> = Item::new("betting_id");
+
+pub struct BettingIndexes<'a> {
+    pub address: MultiIndex<'a, Addr, Betting, u64>,
+    // (market_id, target_height)
+    pub height: MultiIndex<'a, (String, u64), Betting, u64>,
+}
+
+impl<'a> IndexList<Betting> for BettingIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Betting>> + '_> {
+        let v: Vec<&dyn Index<Betting>> = vec![&self.address, &self.height];
+        Box::new(v.into_iter())
+    }
+}
+
+// key - betting id
+pub fn bettings<'a>() -> IndexedMap<'a, u64, Betting, BettingIndexes<'a>> {
+    let indexes = BettingIndexes {
+        address: MultiIndex::new(
+            |_pk: &[u8], betting: &Betting| betting.address.clone(),
+            "bettings",
+            "bettings__address",
+        ),
+        height: MultiIndex::new(
+            |_pk: &[u8], betting: &Betting| (betting.market_id.clone(), betting.target_height),
+            "bettings",
+            "bettings__height",
+        ),
+    };
+    IndexedMap::new("bettings", indexes)
+}
+
+pub fn next_betting_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = BETTING_ID.may_load(storage)?.unwrap_or_default() + 1;
+    BETTING_ID.save(storage, &id)?;
+    Ok(id)
+}
+
+//draw paid by the bank or covered by the house pool, its held winnings can be released
+pub fn remove_draw(storage: &mut dyn Storage, id: u64, draw: &PendingDraw) -> StdResult<()> {
+    DRAWS.remove(storage, id);
+    HEIGHT_DRAWS.remove(
+        storage,
+        (&draw.market_id, draw.height, &denom_key(&draw.denom)),
+    );
+    Ok(())
+}
+
+//shared by PayBacks and draws, the reply id tells them apart
+pub fn next_transfer_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = TRANSFER_ID.may_load(storage)?.unwrap_or_default() + 1;
+    TRANSFER_ID.save(storage, &id)?;
+    Ok(id)
+}
+
+//bettings of one target height, lowest id first, only the given status when Some
+pub fn height_bettings(
+    storage: &dyn Storage,
+    market_id: &str,
+    target_height: u64,
+    status: Option<&BetStatus>,
+    start_after: Option<u64>,
+    limit: Option<usize>,
+) -> StdResult<Vec<Betting>> {
+    let bettings = bettings()
+        .idx
+        .height
+        .prefix((market_id.to_string(), target_height))
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(_, betting)| betting))
+        .filter(|item| match (item, status) {
+            (Ok(betting), Some(status)) => &betting.status == status,
+            _ => true,
+        });
+    match limit {
+        Some(limit) => bettings.take(limit).collect(),
+        None => bettings.collect(),
+    }
+}
+
+// claimable winnings and refunds, withdrawn with ExecuteMsg::Claim
+// key - (address, denom_key)
+pub const BALANCE: Map<(&Addr, &str), Uint128> = Map::new("balance");
+// settlement payouts not released to BALANCE yet
+// key - (address, denom_key, (market_id, target_height))
+pub const HELD_BALANCE: Map<(&Addr, &str, (&str, u64)), Uint128> = Map::new("held_balance");
+// key - (market_id, height)
+pub const PRICES: Map<(&str, u64), Uint128> = Map::new("prices");
+
+// key - (address, denom_key), stake of the address in bettings not settled yet
+pub const OPEN_STAKE: Map<(&Addr, &str), Uint128> = Map::new("open_stake");
+// key - (market_id, target_height, denom_key)
+pub const EXPOSURE: Map<(&str, u64, &str), Exposure> = Map::new("exposure");
+
+// key - denom_key, no entry = no limit
+pub const EXPOSURE_LIMITS: Map<&str, ExposureLimits> = Map::new("exposure_limits");
+
+// key - denom_key
+pub const BANK_LEDGER: Map<&str, BankLedger> = Map::new("bank_ledger");
+
+// key - denom_key
+pub const HOUSE_POOLS: Map<&str, HousePool> = Map::new("house_pools");
+// key - (address, denom_key)
+pub const LP_SHARES: Map<(&Addr, &str), Uint128> = Map::new("lp_shares");
+
+// key - pay back id, also the reply id of its SubMsg
+pub const PAY_BACKS: Map<u64, PendingPayBack> = Map::new("pay_backs");
+// key - draw id, also the reply id of its SubMsg
+pub const DRAWS: Map<u64, PendingDraw> = Map::new("draws");
+// key - (market_id, target_height, denom_key), value - draw id
+pub const HEIGHT_DRAWS: Map<(&str, u64, &str), u64> = Map::new("height_draws");
+pub const TRANSFER_ID: Item<u64> = Item::new("transfer_id");
+
+// key - (market_id, target_height)
+pub const ROUND_RESULTS: Map<(&str, u64), RoundResult> = Map::new("round_results");
+
+// key - (market_id, target_height) with bettings not yet queued for settlement, value - number of bettings
+pub const OPEN_HEIGHTS: Map<(&str, u64), u64> = Map::new("open_heights");
+
+// key - (market_id, target_height) waiting for settlement
+pub const SETTLE_QUEUE: Map<(&str, u64), SettleTask> = Map::new("settle_queue");
+pub const SETTLE_CURSOR: Item<SettleCursor> = Item::new("settle_cursor");
+// key - (market_id, target_height), value - block height the settlement finished at
+pub const SETTLED_HEIGHTS: Map<(&str, u64), u64> = Map::new("settled_heights");
 
-pub const BALANCE: Map<&Addr, Uint128> = Map::new("balance");
-pub const PRICES: Map<u64, Uint128> = Map::new("prices");
+pub const FEEDERS: Map<&Addr, bool> = Map::new("feeders");
+// key - market_id, value - round collecting feeder submissions
+pub const FEED_ROUND: Map<&str, u64> = Map::new("feed_round");
+// key - (market_id, round, feeder), kept after the round is finalized
+pub const FEEDER_SUBMISSIONS: Map<(&str, u64, &Addr), FeederSubmission> =
+    Map::new("feeder_submissions");
+// key - (market_id, round)
+pub const FEED_ROUNDS: Map<(&str, u64), FeedRound> = Map::new("feed_rounds");
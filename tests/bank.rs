@@ -0,0 +1,206 @@
+mod common;
+
+use amg_game::msg::{
+    ExecuteMsg, PendingDrawResponse, PendingPayBackResponse, QueryMsg, SolvencyResponse,
+};
+use amg_game::state::{BankLedger, PoolMode, SignedAmount};
+use amg_game::ContractError;
+use common::{native, setup, Suite, ADMIN, BANK_BALANCE, BETTOR};
+use cosmwasm_std::Uint128;
+
+/*
+Bet 1000 long for 30 blocks at price 1 and settle it at settle_price.
+Won => 1970 paid out, 970 drawn from the bank. Lost => 1000 paid back to the bank.
+ */
+fn bet_and_settle(suite: &mut Suite, settle_price: &str) {
+    suite.bet_long();
+    suite.settle_round(settle_price);
+}
+
+fn ledger(suite: &Suite) -> BankLedger {
+    suite.query(&QueryMsg::GetBankLedger { denom: native() })
+}
+
+fn solvency(suite: &Suite) -> SolvencyResponse {
+    suite.query(&QueryMsg::Solvency { denom: native() })
+}
+
+fn pending_pay_backs(suite: &Suite) -> Vec<PendingPayBackResponse> {
+    suite.query(&QueryMsg::GetPendingPayBacks {
+        start_after: None,
+        limit: None,
+    })
+}
+
+fn pending_draws(suite: &Suite) -> Vec<PendingDrawResponse> {
+    suite.query(&QueryMsg::GetPendingDraws {
+        start_after: None,
+        limit: None,
+    })
+}
+
+fn balance(suite: &Suite) -> u128 {
+    suite.query(&QueryMsg::GetBalance {
+        address: BETTOR.to_string(),
+        denom: native(),
+    })
+}
+
+fn held_balance(suite: &Suite) -> Uint128 {
+    suite.query(&QueryMsg::GetHeldBalance {
+        address: BETTOR.to_string(),
+        denom: native(),
+    })
+}
+
+#[test]
+fn accepted_pay_back_is_removed() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+
+    bet_and_settle(&mut suite, "0.5");
+
+    assert!(pending_pay_backs(&suite).is_empty());
+    let ledger = ledger(&suite);
+    assert_eq!(ledger.paid_back, Uint128::new(1000));
+    assert_eq!(ledger.pending_pay_back, Uint128::zero());
+    assert_eq!(suite.balance(&suite.bank), BANK_BALANCE + 1000);
+
+    let solvency = solvency(&suite);
+    assert_eq!(
+        solvency.total_outstanding,
+        SignedAmount {
+            amount: Uint128::new(1000),
+            negative: true,
+        }
+    );
+    assert_eq!(solvency.bank_repaid, Some(Uint128::new(1000)));
+    assert!(solvency.solvent);
+    assert!(solvency.discrepancies.is_empty());
+}
+
+#[test]
+fn rejected_pay_back_is_queued_and_retried() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite.set_bank_reject(true, false);
+
+    //the settlement goes through, the PayBack waits
+    bet_and_settle(&mut suite, "0.5");
+
+    let pay_backs = pending_pay_backs(&suite);
+    assert_eq!(pay_backs.len(), 1);
+    assert_eq!(pay_backs[0].pay_back.amount, Uint128::new(1000));
+    assert_eq!(pay_backs[0].pay_back.attempts, 1);
+    assert!(pay_backs[0].pay_back.error.is_some());
+    let pending = ledger(&suite);
+    assert_eq!(pending.paid_back, Uint128::zero());
+    assert_eq!(pending.pending_pay_back, Uint128::new(1000));
+    let pending = solvency(&suite);
+    assert_eq!(pending.expected_balance, Uint128::new(1000));
+    assert_eq!(pending.actual_balance, Uint128::new(1000));
+    assert!(pending.discrepancies.is_empty());
+
+    //still rejected, the entry stays
+    suite
+        .execute("anyone", &ExecuteMsg::RetryPayBack { limit: None }, &[])
+        .unwrap();
+    assert_eq!(pending_pay_backs(&suite)[0].pay_back.attempts, 2);
+
+    suite.set_bank_reject(false, false);
+    suite
+        .execute("anyone", &ExecuteMsg::RetryPayBack { limit: None }, &[])
+        .unwrap();
+
+    assert!(pending_pay_backs(&suite).is_empty());
+    let paid = ledger(&suite);
+    assert_eq!(paid.paid_back, Uint128::new(1000));
+    assert_eq!(paid.pending_pay_back, Uint128::zero());
+    assert_eq!(suite.balance(&suite.bank), BANK_BALANCE + 1000);
+
+    let err = suite
+        .execute("anyone", &ExecuteMsg::RetryPayBack { limit: None }, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::NoPayBackToRetry {})
+    ));
+}
+
+#[test]
+fn rejected_draw_holds_winnings_until_retried() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite.set_bank_reject(false, true);
+
+    //the settlement goes through, the winnings wait for the draw
+    bet_and_settle(&mut suite, "2");
+
+    let draws = pending_draws(&suite);
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].draw.amount, Uint128::new(970));
+    assert_eq!(draws[0].draw.attempts, 1);
+    assert!(draws[0].draw.error.is_some());
+    assert_eq!(ledger(&suite).pending_draw, Uint128::new(970));
+    //the 1000 stake is all the contract holds of the 1970 owed
+    let pending = solvency(&suite);
+    assert_eq!(pending.expected_balance, Uint128::new(1000));
+    assert!(pending.solvent);
+    assert_eq!(balance(&suite), 0);
+    assert_eq!(held_balance(&suite), Uint128::new(1970));
+
+    let err = suite.claim(BETTOR).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::NoBalance {})
+    ));
+
+    suite.set_bank_reject(false, false);
+    suite
+        .execute("anyone", &ExecuteMsg::RetryDraw { limit: None }, &[])
+        .unwrap();
+
+    assert!(pending_draws(&suite).is_empty());
+    let drawn = ledger(&suite);
+    assert_eq!(drawn.pending_draw, Uint128::zero());
+    assert_eq!(drawn.drawn, Uint128::new(970));
+    let drawn = solvency(&suite);
+    assert_eq!(
+        drawn.total_outstanding,
+        SignedAmount {
+            amount: Uint128::new(970),
+            negative: false,
+        }
+    );
+    assert_eq!(drawn.bank_borrowed, Some(Uint128::new(970)));
+    assert!(drawn.discrepancies.is_empty());
+    assert_eq!(balance(&suite), 1970);
+    assert_eq!(held_balance(&suite), Uint128::zero());
+
+    suite.claim(BETTOR).unwrap();
+    assert_eq!(suite.balance(BETTOR), 100_970);
+    assert_eq!(suite.balance(&suite.game), 0);
+}
+
+#[test]
+fn bank_contract_is_kept_while_it_has_open_business() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    let swap = ExecuteMsg::SetBankContract {
+        address: "newbank".to_string(),
+    };
+
+    //the open betting reserves from the bank
+    suite.bet_long();
+    let err = suite.execute(ADMIN, &swap, &[]).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::BankInUse {})
+    ));
+
+    //settled, but the draw is not received yet
+    suite.set_bank_reject(false, true);
+    suite.settle_round("2");
+    assert_eq!(ledger(&suite).reserved, Uint128::zero());
+    let err = suite.execute(ADMIN, &swap, &[]).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::BankInUse {})
+    ));
+}
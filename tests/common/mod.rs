@@ -0,0 +1,370 @@
+//scaffolding shared by the multi-test files, not every file uses every helper
+#![allow(dead_code)]
+
+use amg_game::bank::{BankQueryMsg, BorrowedResponse, LiquidityResponse};
+use amg_game::contract::{execute, instantiate, query, reply};
+use amg_game::mock_oracle;
+use amg_game::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use amg_game::state::PoolMode;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coins, to_binary, Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo,
+    Response, StdError, StdResult, Uint128,
+};
+use cw20::Denom;
+use cw_multi_test::{App, AppBuilder, AppResponse, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+pub const ADMIN: &str = "admin";
+pub const BETTOR: &str = "bettor";
+pub const BETTOR2: &str = "bettor2";
+pub const LP: &str = "provider";
+pub const LP2: &str = "provider2";
+pub const MARKET: &str = "btc-usd";
+pub const DENOM: &str = "uusd";
+//what every account but the admin starts with
+pub const START_BALANCE: u128 = 100_000;
+pub const BANK_BALANCE: u128 = 1_000_000;
+
+//bank side of BankExecuteMsg, plus a switch to reject PayBack and BorrowBalance
+#[cw_serde]
+pub enum MockBankMsg {
+    BorrowBalance { denom: Denom, amount: Uint128 },
+    PayBack {},
+    SetReject { pay_back: bool, borrow: bool },
+}
+
+#[cw_serde]
+#[derive(Default)]
+struct Reject {
+    pay_back: bool,
+    borrow: bool,
+}
+
+const REJECT: Item<Reject> = Item::new("reject");
+const BORROWED: Item<Uint128> = Item::new("borrowed");
+const REPAID: Item<Uint128> = Item::new("repaid");
+
+fn bank_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    REJECT.save(deps.storage, &Reject::default())?;
+    BORROWED.save(deps.storage, &Uint128::zero())?;
+    REPAID.save(deps.storage, &Uint128::zero())?;
+    Ok(Response::new())
+}
+
+fn bank_execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: MockBankMsg,
+) -> StdResult<Response> {
+    let reject = REJECT.load(deps.storage)?;
+    match msg {
+        MockBankMsg::BorrowBalance { denom, amount } => {
+            if reject.borrow {
+                return Err(StdError::generic_err("borrow rejected"));
+            }
+            let denom = match denom {
+                Denom::Native(denom) => denom,
+                Denom::Cw20(_) => return Err(StdError::generic_err("native only")),
+            };
+            BORROWED.update(deps.storage, |borrowed| -> StdResult<_> {
+                Ok(borrowed + amount)
+            })?;
+            Ok(Response::new().add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: coins(amount.u128(), denom),
+            }))
+        }
+        MockBankMsg::PayBack {} => {
+            if reject.pay_back {
+                return Err(StdError::generic_err("pay back rejected"));
+            }
+            let amount = info
+                .funds
+                .iter()
+                .fold(Uint128::zero(), |total, coin| total + coin.amount);
+            REPAID.update(deps.storage, |repaid| -> StdResult<_> {
+                Ok(repaid + amount)
+            })?;
+            Ok(Response::new())
+        }
+        MockBankMsg::SetReject { pay_back, borrow } => {
+            REJECT.save(deps.storage, &Reject { pay_back, borrow })?;
+            Ok(Response::new())
+        }
+    }
+}
+
+fn bank_query(deps: Deps, env: Env, msg: BankQueryMsg) -> StdResult<Binary> {
+    match msg {
+        BankQueryMsg::Liquidity { .. } => to_binary(&LiquidityResponse {
+            available: deps
+                .querier
+                .query_balance(env.contract.address, DENOM)?
+                .amount,
+            borrowed: Uint128::zero(),
+        }),
+        BankQueryMsg::Borrowed { .. } => to_binary(&BorrowedResponse {
+            borrowed: BORROWED.load(deps.storage)?,
+            repaid: REPAID.load(deps.storage)?,
+        }),
+        BankQueryMsg::LpTokenInfo {} => Err(StdError::generic_err("no lp token")),
+    }
+}
+
+fn game_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(execute, instantiate, query).with_reply(reply))
+}
+
+fn bank_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        bank_execute,
+        bank_instantiate,
+        bank_query,
+    ))
+}
+
+fn oracle_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        mock_oracle::execute,
+        mock_oracle::instantiate,
+        mock_oracle::query,
+    ))
+}
+
+pub fn native() -> Denom {
+    Denom::Native(DENOM.to_string())
+}
+
+pub struct Suite {
+    pub app: App,
+    pub game: Addr,
+    pub bank: Addr,
+    pub oracle: Option<Addr>,
+}
+
+/*
+Game with one market MARKET and DENOM, a funded mock bank and,
+when oracle is set, a mock oracle as price source instead of the admin.
+ */
+pub fn setup(pool_mode: PoolMode, oracle: bool) -> Suite {
+    let mut app = AppBuilder::new().build(|router, _, storage| {
+        router
+            .bank
+            .init_balance(
+                storage,
+                &Addr::unchecked(ADMIN),
+                coins(BANK_BALANCE * 10, DENOM),
+            )
+            .unwrap();
+        for address in [BETTOR, BETTOR2, LP, LP2] {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(address),
+                    coins(START_BALANCE, DENOM),
+                )
+                .unwrap();
+        }
+    });
+    let admin = Addr::unchecked(ADMIN);
+
+    let bank_id = app.store_code(bank_contract());
+    let bank = app
+        .instantiate_contract(
+            bank_id,
+            admin.clone(),
+            &Empty {},
+            &coins(BANK_BALANCE, DENOM),
+            "bank",
+            None,
+        )
+        .unwrap();
+
+    let oracle = match oracle {
+        true => {
+            let oracle_id = app.store_code(oracle_contract());
+            Some(
+                app.instantiate_contract(
+                    oracle_id,
+                    admin.clone(),
+                    &mock_oracle::InstantiateMsg {},
+                    &[],
+                    "oracle",
+                    None,
+                )
+                .unwrap(),
+            )
+        }
+        false => None,
+    };
+
+    let game_id = app.store_code(game_contract());
+    let game = app
+        .instantiate_contract(
+            game_id,
+            admin,
+            &InstantiateMsg {
+                denom: DENOM.to_string(),
+                market_id: MARKET.to_string(),
+                fee_late: 3,
+                oracle_address: oracle.as_ref().map(|oracle| oracle.to_string()),
+                price: Decimal::one(),
+                minimum_amount: 100,
+                bank_contract_address: bank.to_string(),
+                price_precision: None,
+                settle_limit: None,
+                missed_round_policy: None,
+                refund_timeout: None,
+                max_pool_share_bps: None,
+                pool_mode: Some(pool_mode),
+            },
+            &[],
+            "game",
+            None,
+        )
+        .unwrap();
+
+    Suite {
+        app,
+        game,
+        bank,
+        oracle,
+    }
+}
+
+impl Suite {
+    pub fn execute<T: Serialize + Debug>(
+        &mut self,
+        sender: &str,
+        msg: &T,
+        funds: &[Coin],
+    ) -> anyhow::Result<AppResponse> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), self.game.clone(), msg, funds)
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, msg: &QueryMsg) -> T {
+        self.app.wrap().query_wasm_smart(&self.game, msg).unwrap()
+    }
+
+    pub fn balance(&self, address: impl Into<String>) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(address, DENOM)
+            .unwrap()
+            .amount
+            .u128()
+    }
+
+    pub fn next_block(&mut self, blocks: u64) {
+        self.app.update_block(|block| block.height += blocks);
+    }
+
+    pub fn height(&self) -> u64 {
+        self.app.block_info().height
+    }
+
+    pub fn setting(&mut self, price: &str) -> anyhow::Result<AppResponse> {
+        self.execute(
+            ADMIN,
+            &ExecuteMsg::Setting {
+                market_id: MARKET.to_string(),
+                price: Decimal::from_str(price).unwrap(),
+                lock: None,
+            },
+            &[],
+        )
+    }
+
+    //admin price of the next height
+    pub fn set_price(&mut self, price: &str) {
+        self.setting(price).unwrap();
+    }
+
+    pub fn set_oracle_price(&mut self, price: &str) {
+        let oracle = self.oracle.clone().unwrap();
+        self.app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                oracle,
+                &mock_oracle::ExecuteMsg::SetPrice {
+                    market_id: MARKET.to_string(),
+                    price: Decimal::from_str(price).unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn set_bank_reject(&mut self, pay_back: bool, borrow: bool) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                self.bank.clone(),
+                &MockBankMsg::SetReject { pay_back, borrow },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn bet(
+        &mut self,
+        bettor: &str,
+        position: &str,
+        duration: u64,
+        amount: u128,
+    ) -> anyhow::Result<AppResponse> {
+        self.execute(
+            bettor,
+            &ExecuteMsg::Betting {
+                market_id: MARKET.to_string(),
+                position: position.to_string(),
+                duration,
+            },
+            &coins(amount, DENOM),
+        )
+    }
+
+    pub fn settle(&mut self, limit: u64) -> anyhow::Result<AppResponse> {
+        self.execute("anyone", &ExecuteMsg::Settle { limit }, &[])
+    }
+
+    //1000 long for 30 blocks at price 1, win amount 1970
+    pub fn bet_long(&mut self) {
+        self.set_price("1");
+        self.next_block(1);
+        self.bet(BETTOR, "long", 30, 1000).unwrap();
+    }
+
+    //price the target height of bet_long, queue it with the next setting and settle it
+    pub fn settle_round(&mut self, settle_price: &str) {
+        self.next_block(29);
+        self.set_price(settle_price);
+        self.next_block(1);
+        self.set_price(settle_price);
+        self.settle(10).unwrap();
+    }
+
+    pub fn claim(&mut self, address: &str) -> anyhow::Result<AppResponse> {
+        self.execute(
+            address,
+            &ExecuteMsg::Claim {
+                denom: native(),
+                amount: None,
+            },
+            &[],
+        )
+    }
+}
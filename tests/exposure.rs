@@ -0,0 +1,84 @@
+mod common;
+
+use amg_game::msg::ExecuteMsg;
+use amg_game::state::{ExposureLimits, PoolMode};
+use amg_game::ContractError;
+use common::{native, setup, Suite, ADMIN, BETTOR, BETTOR2};
+use cosmwasm_std::Uint128;
+use cw_multi_test::AppResponse;
+
+fn limited(limits: ExposureLimits) -> Suite {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite
+        .execute(
+            ADMIN,
+            &ExecuteMsg::SetExposureLimits {
+                denom: native(),
+                limits,
+            },
+            &[],
+        )
+        .unwrap();
+    suite.set_price("1");
+    suite.next_block(1);
+    suite
+}
+
+fn error(result: anyhow::Result<AppResponse>) -> ContractError {
+    result.unwrap_err().downcast::<ContractError>().unwrap()
+}
+
+#[test]
+fn betting_above_max_bet_amount_is_rejected() {
+    let mut suite = limited(ExposureLimits {
+        max_bet_amount: Some(Uint128::new(1000)),
+        ..ExposureLimits::default()
+    });
+
+    assert!(matches!(
+        error(suite.bet(BETTOR, "long", 30, 1001)),
+        ContractError::ExceedMaxBetAmount { max_bet_amount } if max_bet_amount == Uint128::new(1000)
+    ));
+    suite.bet(BETTOR, "long", 30, 1000).unwrap();
+}
+
+#[test]
+fn open_stake_above_max_open_stake_is_rejected() {
+    let mut suite = limited(ExposureLimits {
+        max_open_stake: Some(Uint128::new(2500)),
+        ..ExposureLimits::default()
+    });
+
+    //counted over every open betting of the address
+    suite.bet(BETTOR, "long", 30, 1000).unwrap();
+    suite.bet(BETTOR, "short", 50, 1000).unwrap();
+    assert!(matches!(
+        error(suite.bet(BETTOR, "long", 30, 1000)),
+        ContractError::ExceedMaxOpenStake { max_open_stake } if max_open_stake == Uint128::new(2500)
+    ));
+    suite.bet(BETTOR, "long", 30, 500).unwrap();
+    suite.bet(BETTOR2, "long", 30, 1000).unwrap();
+}
+
+#[test]
+fn net_exposure_above_max_net_exposure_is_rejected() {
+    let mut suite = limited(ExposureLimits {
+        max_net_exposure: Some(Uint128::new(1500)),
+        ..ExposureLimits::default()
+    });
+
+    suite.bet(BETTOR, "long", 30, 1000).unwrap();
+    assert!(matches!(
+        error(suite.bet(BETTOR2, "long", 30, 1000)),
+        ContractError::ExceedMaxNetExposure { max_net_exposure } if max_net_exposure == Uint128::new(1500)
+    ));
+
+    //short 2000 against long 1000, then short is the crowded side
+    suite.bet(BETTOR2, "short", 30, 2000).unwrap();
+    assert!(matches!(
+        error(suite.bet(BETTOR2, "short", 30, 1000)),
+        ContractError::ExceedMaxNetExposure { .. }
+    ));
+    //the smaller side can still bet
+    suite.bet(BETTOR, "long", 30, 2000).unwrap();
+}
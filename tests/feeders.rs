@@ -0,0 +1,132 @@
+mod common;
+
+use amg_game::msg::{ExecuteMsg, FeedRoundResponse, PriceResponse, QueryMsg};
+use amg_game::state::{PoolMode, PriceSource};
+use common::{setup, Suite, ADMIN, MARKET};
+use cosmwasm_std::Decimal;
+use std::str::FromStr;
+
+const FEEDERS: [&str; 4] = ["feeder1", "feeder2", "feeder3", "feeder4"];
+
+fn feeders_setup(quorum: u32, max_age: Option<u64>) -> Suite {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    for feeder in FEEDERS {
+        suite
+            .execute(
+                ADMIN,
+                &ExecuteMsg::AddFeeder {
+                    address: feeder.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+    suite
+        .execute(
+            ADMIN,
+            &ExecuteMsg::SetPriceSource {
+                source: PriceSource::Feeders { quorum, max_age },
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+}
+
+fn submit(suite: &mut Suite, feeder: &str, price: &str) {
+    suite
+        .execute(
+            feeder,
+            &ExecuteMsg::SubmitPrice {
+                market_id: MARKET.to_string(),
+                price: Decimal::from_str(price).unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+}
+
+fn feed_round(suite: &Suite, round: Option<u64>) -> FeedRoundResponse {
+    suite.query(&QueryMsg::GetFeedRound {
+        market_id: MARKET.to_string(),
+        round,
+    })
+}
+
+fn latest_price(suite: &Suite) -> Decimal {
+    let latest: PriceResponse = suite.query(&QueryMsg::GetLatestPrice {
+        market_id: MARKET.to_string(),
+    });
+    latest.price
+}
+
+#[test]
+fn round_is_priced_once_quorum_feeders_submitted() {
+    let mut suite = feeders_setup(3, None);
+
+    submit(&mut suite, FEEDERS[0], "1.1");
+    submit(&mut suite, FEEDERS[1], "1.2");
+    let open = feed_round(&suite, None);
+    assert_eq!(open.round, 1);
+    assert_eq!(open.price, None);
+    assert_eq!(open.submissions.len(), 2);
+    assert_eq!(latest_price(&suite), Decimal::one());
+
+    //the third submission reaches quorum, an outlier does not move the median
+    submit(&mut suite, FEEDERS[2], "5");
+    let priced = feed_round(&suite, Some(1));
+    assert_eq!(
+        priced.price.unwrap().price,
+        Decimal::from_str("1.2").unwrap()
+    );
+    assert_eq!(latest_price(&suite), Decimal::from_str("1.2").unwrap());
+    assert_eq!(feed_round(&suite, None).round, 2);
+}
+
+#[test]
+fn even_submissions_are_priced_at_the_middle_of_the_two_medians() {
+    let mut suite = feeders_setup(4, None);
+
+    for (feeder, price) in FEEDERS.iter().zip(["1", "2", "4", "10"]) {
+        submit(&mut suite, feeder, price);
+    }
+    let priced = feed_round(&suite, Some(1));
+    assert_eq!(priced.price.unwrap().price, Decimal::from_str("3").unwrap());
+    assert_eq!(latest_price(&suite), Decimal::from_str("3").unwrap());
+}
+
+#[test]
+fn stale_submissions_are_dropped() {
+    let mut suite = feeders_setup(2, Some(5));
+
+    submit(&mut suite, FEEDERS[0], "1");
+    suite.next_block(6);
+
+    //the first submission is too old to make quorum with this one
+    submit(&mut suite, FEEDERS[1], "2");
+    assert_eq!(feed_round(&suite, None).price, None);
+
+    //nor to count toward the median once quorum is reached
+    submit(&mut suite, FEEDERS[2], "3");
+    let priced = feed_round(&suite, Some(1));
+    assert_eq!(
+        priced.price.unwrap().price,
+        Decimal::from_str("2.5").unwrap()
+    );
+}
+
+#[test]
+fn a_feeder_replaces_its_own_submission() {
+    let mut suite = feeders_setup(2, None);
+
+    submit(&mut suite, FEEDERS[0], "1");
+    submit(&mut suite, FEEDERS[0], "2");
+    let open = feed_round(&suite, None);
+    assert_eq!(open.price, None);
+    assert_eq!(open.submissions.len(), 1);
+    assert_eq!(open.submissions[0].price, Decimal::from_str("2").unwrap());
+
+    submit(&mut suite, FEEDERS[1], "4");
+    let priced = feed_round(&suite, Some(1));
+    assert_eq!(priced.price.unwrap().price, Decimal::from_str("3").unwrap());
+}
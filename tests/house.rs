@@ -0,0 +1,82 @@
+mod common;
+
+use amg_game::msg::{ExecuteMsg, HousePoolResponse, LpSharesResponse, QueryMsg};
+use amg_game::state::PoolMode;
+use common::{native, setup, Suite, BETTOR, DENOM, LP, LP2};
+use cosmwasm_std::{coins, Uint128};
+
+fn deposit(suite: &mut Suite, lp: &str, amount: u128) {
+    suite
+        .execute(lp, &ExecuteMsg::Deposit {}, &coins(amount, DENOM))
+        .unwrap();
+}
+
+fn withdraw(suite: &mut Suite, lp: &str, shares: Uint128) {
+    suite
+        .execute(
+            lp,
+            &ExecuteMsg::Withdraw {
+                denom: native(),
+                shares,
+            },
+            &[],
+        )
+        .unwrap();
+}
+
+fn lp_shares(suite: &Suite, lp: &str) -> LpSharesResponse {
+    suite.query(&QueryMsg::GetLpShares {
+        address: lp.to_string(),
+        denom: native(),
+    })
+}
+
+fn house_pool(suite: &Suite) -> HousePoolResponse {
+    suite.query(&QueryMsg::GetHousePool { denom: native() })
+}
+
+#[test]
+fn withdraw_pays_free_liquidity_while_bettings_are_open() {
+    let mut suite = setup(PoolMode::House {}, false);
+    deposit(&mut suite, LP, 10_000);
+    //970 of the win amount reserved from the pool
+    suite.bet_long();
+
+    let pool = house_pool(&suite);
+    assert_eq!(pool.reserved, Uint128::new(970));
+    assert_eq!(pool.free, Uint128::new(9030));
+
+    //every share asked, only the free part is paid and the shares worth it burned
+    let owned = lp_shares(&suite, LP);
+    withdraw(&mut suite, LP, owned.shares);
+    assert_eq!(suite.balance(LP), 90_000 + 9029);
+    let left = lp_shares(&suite, LP);
+    assert_eq!(left.value, Uint128::new(971));
+    assert_eq!(left.withdrawable, Uint128::zero());
+
+    //the lost stake goes to the pool and the rest can leave
+    suite.settle_round("0.5");
+    let left = lp_shares(&suite, LP);
+    assert_eq!(left.value, Uint128::new(1969));
+    assert_eq!(left.withdrawable, Uint128::new(1968));
+    withdraw(&mut suite, LP, left.shares);
+    assert_eq!(suite.balance(LP), 90_000 + 9029 + 1968);
+}
+
+#[test]
+fn deposit_after_a_winning_round_keeps_the_share_price() {
+    let mut suite = setup(PoolMode::House {}, false);
+    deposit(&mut suite, LP, 10_000);
+    suite.bet_long();
+    suite.settle_round("2");
+
+    //the pool paid 970 of the 1970 won, the winnings are claimable right away
+    assert_eq!(house_pool(&suite).value, Uint128::new(9030));
+    suite.claim(BETTOR).unwrap();
+    assert_eq!(suite.balance(BETTOR), 100_970);
+
+    //the new depositor buys in at the current value, the first one keeps theirs
+    deposit(&mut suite, LP2, 9030);
+    assert_eq!(lp_shares(&suite, LP).value, Uint128::new(9030));
+    assert_eq!(lp_shares(&suite, LP2).value, Uint128::new(9029));
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use amg_game::msg::{ExecuteMsg, PriceResponse, QueryMsg};
+use amg_game::state::PoolMode;
+use amg_game::ContractError;
+use common::{setup, ADMIN, BETTOR, DENOM, MARKET};
+use cosmwasm_std::{coins, Decimal};
+
+fn invalid_duration(result: anyhow::Result<cw_multi_test::AppResponse>) -> bool {
+    matches!(
+        result.unwrap_err().downcast_ref::<ContractError>(),
+        Some(ContractError::InvalidDuration {})
+    )
+}
+
+#[test]
+fn durations_shorter_than_two_blocks_are_rejected() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+
+    for duration in [0, 1] {
+        let added = suite.execute(
+            ADMIN,
+            &ExecuteMsg::AddMarket {
+                market_id: "eth-usd".to_string(),
+                fee_late: 3,
+                durations: vec![30, duration],
+                price_precision: None,
+            },
+            &[],
+        );
+        assert!(invalid_duration(added));
+
+        let set = suite.execute(
+            ADMIN,
+            &ExecuteMsg::SetDuration {
+                market_id: MARKET.to_string(),
+                duration,
+                fee_late: 3,
+            },
+            &[],
+        );
+        assert!(invalid_duration(set));
+    }
+
+    suite
+        .execute(
+            ADMIN,
+            &ExecuteMsg::SetDuration {
+                market_id: MARKET.to_string(),
+                duration: 2,
+                fee_late: 3,
+            },
+            &[],
+        )
+        .unwrap();
+}
+
+#[test]
+fn bets_wait_for_the_first_price_of_a_market() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite
+        .execute(
+            ADMIN,
+            &ExecuteMsg::AddMarket {
+                market_id: "eth-usd".to_string(),
+                fee_late: 3,
+                durations: vec![30],
+                price_precision: None,
+            },
+            &[],
+        )
+        .unwrap();
+    let bet = ExecuteMsg::Betting {
+        market_id: "eth-usd".to_string(),
+        position: "long".to_string(),
+        duration: 30,
+    };
+
+    let err = suite
+        .execute(BETTOR, &bet, &coins(1000, DENOM))
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::NoMarketPrice {})
+    ));
+
+    suite
+        .execute(
+            ADMIN,
+            &ExecuteMsg::Setting {
+                market_id: "eth-usd".to_string(),
+                price: Decimal::one(),
+                lock: None,
+            },
+            &[],
+        )
+        .unwrap();
+    suite.next_block(1);
+    suite.execute(BETTOR, &bet, &coins(1000, DENOM)).unwrap();
+}
+
+#[test]
+fn the_first_market_starts_at_the_instantiate_price() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+
+    let latest: PriceResponse = suite.query(&QueryMsg::GetLatestPrice {
+        market_id: MARKET.to_string(),
+    });
+    assert_eq!(latest.price, Decimal::one());
+    assert_eq!(latest.height, suite.height());
+
+    suite.next_block(1);
+    suite.bet(BETTOR, "long", 30, 1000).unwrap();
+}
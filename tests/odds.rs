@@ -0,0 +1,123 @@
+mod common;
+
+use amg_game::msg::{ExecuteMsg, OddsResponse, QueryMsg, QuoteResponse};
+use amg_game::state::{Betting, OddsMode, PoolMode};
+use amg_game::ContractError;
+use common::{native, setup, Suite, ADMIN, BETTOR, MARKET};
+use cosmwasm_std::{Decimal, Uint128};
+use std::str::FromStr;
+
+fn quote(suite: &Suite, position: &str, duration: u64, amount: u128) -> QuoteResponse {
+    suite.query(&QueryMsg::QuoteBet {
+        market_id: MARKET.to_string(),
+        position: position.to_string(),
+        duration,
+        amount: Uint128::new(amount),
+        denom: native(),
+        address: Some(BETTOR.to_string()),
+    })
+}
+
+fn bets(suite: &Suite) -> Vec<Betting> {
+    suite.query(&QueryMsg::BetsByAddress {
+        address: BETTOR.to_string(),
+        status: None,
+        start_after: None,
+        limit: None,
+    })
+}
+
+#[test]
+fn dynamic_odds_price_the_bet_on_the_exposure_after_it() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite
+        .execute(
+            ADMIN,
+            &ExecuteMsg::SetOddsMode {
+                mode: OddsMode::Dynamic {
+                    sensitivity_bps: 5000,
+                    min_payout_bps: 0,
+                    max_payout_bps: 20000,
+                },
+            },
+            &[],
+        )
+        .unwrap();
+    suite.set_price("1");
+    suite.next_block(1);
+
+    //a lone long is fully skewed: 0.97 - 0.5
+    let odds: OddsResponse = suite.query(&QueryMsg::GetOdds {
+        market_id: MARKET.to_string(),
+        duration: 30,
+        amount: Uint128::new(1000),
+        denom: native(),
+    });
+    assert_eq!(odds.long, Decimal::from_str("1.47").unwrap());
+    let quoted = quote(&suite, "long", 30, 1000);
+    assert_eq!(quoted.win_amount, Some(Uint128::new(1470)));
+
+    suite.bet(BETTOR, "long", 30, 1000).unwrap();
+    assert_eq!(bets(&suite)[0].win_amount, Uint128::new(1470));
+
+    //3000 short against 1000 long leaves short crowded by half: 0.97 - 0.25
+    let odds: OddsResponse = suite.query(&QueryMsg::GetOdds {
+        market_id: MARKET.to_string(),
+        duration: 30,
+        amount: Uint128::new(3000),
+        denom: native(),
+    });
+    assert_eq!(odds.short, Decimal::from_str("1.72").unwrap());
+    let quoted = quote(&suite, "short", 30, 3000);
+    assert_eq!(quoted.win_amount, Some(Uint128::new(5160)));
+
+    suite.bet(BETTOR, "short", 30, 3000).unwrap();
+    assert_eq!(bets(&suite)[1].win_amount, Uint128::new(5160));
+}
+
+//the error Betting fails with, as QuoteBet reports it
+fn bet_error(suite: &mut Suite, position: &str, duration: u64, amount: u128) -> String {
+    suite
+        .bet(BETTOR, position, duration, amount)
+        .unwrap_err()
+        .downcast::<ContractError>()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn quote_matches_the_betting_it_dry_runs() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite.set_price("1.5");
+    suite.next_block(1);
+
+    let quoted = quote(&suite, "short", 50, 2000);
+    assert_eq!(quoted.error, None);
+    assert_eq!(quoted.target_height, suite.height() + 50);
+    assert_eq!(quoted.base_price, Some(Decimal::from_str("1.5").unwrap()));
+    suite.bet(BETTOR, "short", 50, 2000).unwrap();
+    let betting = &bets(&suite)[0];
+    assert_eq!(quoted.win_amount, Some(betting.win_amount));
+    assert_eq!(quoted.target_height, betting.target_height);
+
+    //no such duration
+    let quoted = quote(&suite, "long", 40, 1000);
+    assert_eq!(quoted.win_amount, None);
+    assert_eq!(quoted.error, Some(bet_error(&mut suite, "long", 40, 1000)));
+
+    //locked market
+    suite
+        .execute(
+            ADMIN,
+            &ExecuteMsg::SetLock {
+                market_id: MARKET.to_string(),
+                lock: true,
+            },
+            &[],
+        )
+        .unwrap();
+    let quoted = quote(&suite, "long", 30, 1000);
+    assert_eq!(quoted.win_amount, None);
+    assert_eq!(quoted.error, Some(bet_error(&mut suite, "long", 30, 1000)));
+    assert_eq!(quoted.error, Some(ContractError::Lock {}.to_string()));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use amg_game::msg::{ExecuteMsg, PriceResponse, QueryMsg};
+use amg_game::state::PoolMode;
+use amg_game::ContractError;
+use common::{setup, Suite, MARKET};
+use cosmwasm_std::Decimal;
+use std::str::FromStr;
+
+fn update_price(suite: &mut Suite) -> anyhow::Result<()> {
+    suite
+        .execute(
+            "anyone",
+            &ExecuteMsg::UpdatePrice {
+                market_id: MARKET.to_string(),
+            },
+            &[],
+        )
+        .map(|_| ())
+}
+
+#[test]
+fn update_price_rounds_oracle_price_to_market_precision() {
+    let mut suite = setup(PoolMode::Bank {}, true);
+
+    suite.set_oracle_price("1.234567891234567891");
+    update_price(&mut suite).unwrap();
+
+    let latest: PriceResponse = suite.query(&QueryMsg::GetLatestPrice {
+        market_id: MARKET.to_string(),
+    });
+    assert_eq!(latest.price, Decimal::from_str("1.23457").unwrap());
+    assert_eq!(latest.precision, 5);
+}
+
+#[test]
+fn update_price_rejects_stale_oracle_price() {
+    let mut suite = setup(PoolMode::Bank {}, true);
+
+    suite.set_oracle_price("1.5");
+    let set_height = suite.height();
+    suite.next_block(11);
+
+    let err = update_price(&mut suite).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::StaleOraclePrice { height }) if *height == set_height
+    ));
+
+    //a fresh price goes through again
+    suite.set_oracle_price("1.6");
+    update_price(&mut suite).unwrap();
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use amg_game::msg::{ExecuteMsg, MarketResponse, PriceResponse, QueryMsg};
+use amg_game::state::{PoolMode, PriceGuard};
+use amg_game::ContractError;
+use common::{setup, Suite, ADMIN, BETTOR, MARKET};
+use cosmwasm_std::Uint128;
+
+fn set_guard(suite: &mut Suite, lock_on_violation: bool) {
+    suite
+        .execute(
+            ADMIN,
+            &ExecuteMsg::SetPriceGuard {
+                guard: PriceGuard {
+                    max_deviation_bps: Some(1000),
+                    max_block_gap: None,
+                    lock_on_violation,
+                },
+            },
+            &[],
+        )
+        .unwrap();
+}
+
+fn market(suite: &Suite) -> MarketResponse {
+    suite.query(&QueryMsg::GetMarket {
+        market_id: MARKET.to_string(),
+    })
+}
+
+#[test]
+fn out_of_band_price_is_rejected() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    set_guard(&mut suite, false);
+
+    let err = suite.setting("1.5").unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::PriceDeviation { .. })
+    ));
+    assert!(!market(&suite).market.lock);
+    suite.setting("1.05").unwrap();
+}
+
+#[test]
+fn out_of_band_price_locks_the_market_on_violation() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    set_guard(&mut suite, true);
+    let height = suite.height();
+
+    //the Setting goes through, the price is dropped and the market locked
+    suite.setting("1.5").unwrap();
+    let locked = market(&suite).market;
+    assert!(locked.lock);
+    assert_eq!(locked.latest_price, Uint128::new(100_000));
+    assert_eq!(locked.latest_price_height, height);
+    let round_price = suite.app.wrap().query_wasm_smart::<PriceResponse>(
+        &suite.game,
+        &QueryMsg::GetRoundPrice {
+            market_id: MARKET.to_string(),
+            height: height + 1,
+        },
+    );
+    assert!(round_price.is_err());
+
+    suite.next_block(1);
+    let err = suite.bet(BETTOR, "long", 30, 1000).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ContractError>(),
+        Some(ContractError::Lock {})
+    ));
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use amg_game::msg::{ExecuteMsg, PreviewSettlementResponse, QueryMsg, RoundResultResponse};
+use amg_game::state::{BetStatus, Betting, MissedRoundPolicy, PoolMode, PriceRange};
+use common::{native, setup, Suite, ADMIN, BETTOR, BETTOR2, MARKET};
+use cosmwasm_std::{Decimal, Uint128};
+use cw_multi_test::AppResponse;
+use std::str::FromStr;
+
+fn attr(response: &AppResponse, key: &str) -> Option<String> {
+    response
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == key)
+        .map(|attr| attr.value.clone())
+}
+
+fn balance(suite: &Suite, address: &str) -> u128 {
+    suite.query(&QueryMsg::GetBalance {
+        address: address.to_string(),
+        denom: native(),
+    })
+}
+
+fn bets(suite: &Suite, address: &str) -> Vec<Betting> {
+    suite.query(&QueryMsg::BetsByAddress {
+        address: address.to_string(),
+        status: None,
+        start_after: None,
+        limit: None,
+    })
+}
+
+fn round_result(suite: &Suite, height: u64) -> RoundResultResponse {
+    suite.query(&QueryMsg::GetRoundResult {
+        market_id: MARKET.to_string(),
+        height,
+    })
+}
+
+#[test]
+fn settle_resolves_the_last_priced_height_after_an_outage() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite.bet_long();
+
+    //the last Setting prices the target height, then the feed stops
+    suite.next_block(29);
+    suite.set_price("2");
+    suite.next_block(5);
+    suite.settle(10).unwrap();
+
+    assert_eq!(balance(&suite, BETTOR), 1970);
+}
+
+#[test]
+fn settle_spreads_a_height_over_several_calls() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite.set_price("1");
+    suite.next_block(1);
+    let target_height = suite.height() + 30;
+    for _ in 0..3 {
+        suite.bet(BETTOR, "long", 30, 1000).unwrap();
+    }
+    for _ in 0..2 {
+        suite.bet(BETTOR2, "short", 30, 1000).unwrap();
+    }
+    suite.next_block(29);
+    suite.set_price("2");
+    suite.next_block(1);
+    suite.set_price("2");
+
+    //five bettings, two per call, the cursor picks up after the last one settled
+    for settled in [2, 4] {
+        let response = suite.settle(2).unwrap();
+        assert_eq!(attr(&response, "settled"), Some("2".to_string()));
+        assert_eq!(attr(&response, "settled_height"), None);
+        assert_eq!(round_result(&suite, target_height).result.bettings, settled);
+    }
+    //the bank is squared once, when the height is done
+    assert_eq!(balance(&suite, BETTOR), 0);
+
+    let response = suite.settle(2).unwrap();
+    assert_eq!(attr(&response, "settled"), Some("1".to_string()));
+    assert_eq!(
+        attr(&response, "settled_height"),
+        Some(format!("{}/{}", MARKET, target_height))
+    );
+    assert_eq!(round_result(&suite, target_height).result.bettings, 5);
+    assert!(bets(&suite, BETTOR)
+        .iter()
+        .all(|betting| betting.status == BetStatus::Won));
+    assert!(bets(&suite, BETTOR2)
+        .iter()
+        .all(|betting| betting.status == BetStatus::Lost));
+    assert_eq!(balance(&suite, BETTOR), 3 * 1970);
+}
+
+#[test]
+fn a_height_is_settled_only_once() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite.bet_long();
+    let target_height = suite.height() + 30;
+    suite.settle_round("2");
+    assert_eq!(balance(&suite, BETTOR), 1970);
+    let settled = round_result(&suite, target_height);
+
+    //later prices and settle calls leave the height alone
+    suite.next_block(1);
+    suite.set_price("0.5");
+    let response = suite.settle(10).unwrap();
+    assert_eq!(attr(&response, "settled"), Some("0".to_string()));
+    assert_eq!(round_result(&suite, target_height), settled);
+    assert_eq!(balance(&suite, BETTOR), 1970);
+    assert_eq!(bets(&suite, BETTOR)[0].status, BetStatus::Won);
+}
+
+/*
+Bet 1000 long at price 1 and skip the Setting that prices its target height.
+The Setting at the target height prices the next one at 0.5.
+ */
+fn miss_target_height(policy: MissedRoundPolicy) -> Suite {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite
+        .execute(ADMIN, &ExecuteMsg::SetMissedRoundPolicy { policy }, &[])
+        .unwrap();
+    suite.bet_long();
+    suite.next_block(28);
+    suite.set_price("2");
+    suite.next_block(2);
+    suite.set_price("0.5");
+    suite.settle(10).unwrap();
+    suite
+}
+
+#[test]
+fn next_price_policy_settles_a_missed_height_at_the_next_price() {
+    let suite = miss_target_height(MissedRoundPolicy::NextPrice {});
+
+    let betting = &bets(&suite, BETTOR)[0];
+    assert_eq!(betting.status, BetStatus::Lost);
+    assert_eq!(
+        betting.settle_price,
+        Some(betting.base_price / Uint128::new(2))
+    );
+    assert_eq!(balance(&suite, BETTOR), 0);
+}
+
+#[test]
+fn refund_policy_refunds_a_missed_height() {
+    let suite = miss_target_height(MissedRoundPolicy::Refund {});
+
+    let betting = &bets(&suite, BETTOR)[0];
+    assert_eq!(betting.status, BetStatus::Refunded);
+    assert_eq!(betting.settle_price, None);
+    assert_eq!(balance(&suite, BETTOR), 1000);
+}
+
+#[test]
+fn preview_matches_the_settlement() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite.set_price("1");
+    suite.next_block(1);
+    let target_height = suite.height() + 30;
+    suite.bet(BETTOR, "long", 30, 1000).unwrap();
+    suite.bet(BETTOR, "short", 30, 500).unwrap();
+    suite.bet(BETTOR2, "short", 30, 2000).unwrap();
+    suite.bet(BETTOR2, "long", 30, 300).unwrap();
+    suite.next_block(29);
+    suite.set_price("2");
+    suite.next_block(1);
+    suite.set_price("2");
+
+    let preview: PreviewSettlementResponse = suite.query(&QueryMsg::PreviewSettlement {
+        market_id: MARKET.to_string(),
+        height: target_height,
+        price: Decimal::from_str("2").unwrap(),
+    });
+    suite.settle(10).unwrap();
+    let result = round_result(&suite, target_height).result;
+
+    assert_eq!(result.settle_price, Some(Uint128::new(200_000)));
+    assert_eq!(preview.winners, 2);
+    assert_eq!(result.bettings, preview.winners + preview.losers);
+    assert_eq!(result.total_paid, preview.payouts);
+    assert_eq!(result.total_returned, preview.pay_back);
+    assert_eq!(result.total_drawn, preview.draw);
+    for account in preview.accounts {
+        assert_eq!(
+            balance(&suite, account.address.as_str()),
+            account.amount.u128()
+        );
+    }
+}
+
+#[test]
+fn round_result_keeps_the_range_of_base_prices() {
+    let mut suite = setup(PoolMode::Bank {}, false);
+    suite.set_price("1");
+    suite.next_block(1);
+    let target_height = suite.height() + 50;
+    suite.bet(BETTOR, "long", 50, 1000).unwrap();
+
+    //a 30 block betting on the same height starts 20 blocks later, from another price
+    suite.set_price("1.2");
+    suite.next_block(20);
+    suite.bet(BETTOR2, "long", 30, 1000).unwrap();
+    suite.next_block(29);
+    suite.set_price("1.1");
+    suite.next_block(1);
+    suite.set_price("1.1");
+    suite.settle(10).unwrap();
+
+    let result = round_result(&suite, target_height).result;
+    assert_eq!(
+        result.base_price,
+        Some(PriceRange {
+            low: Uint128::new(100_000),
+            high: Uint128::new(120_000),
+        })
+    );
+    assert_eq!(result.settle_price, Some(Uint128::new(110_000)));
+}